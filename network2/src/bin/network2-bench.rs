@@ -0,0 +1,308 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter},
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::{Parser, ValueEnum};
+use mimalloc::MiMalloc;
+use network2::{Collective, Id, Participant, TcpTopology, TopologyKind};
+use rand::RngCore;
+
+#[global_allocator]
+static GLOBAL: MiMalloc = MiMalloc;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Transport {
+    Tcp,
+    Noise,
+    Quic,
+}
+
+impl Transport {
+    fn name(self) -> &'static str {
+        match self {
+            Transport::Tcp => "tcp",
+            Transport::Noise => "noise",
+            Transport::Quic => "quic",
+        }
+    }
+}
+
+#[derive(Parser)]
+struct Cli {
+    #[arg(short, long)]
+    id: Id,
+    #[arg(short, long)]
+    party_count: Option<usize>,
+    #[arg(short, long)]
+    config_path: Option<PathBuf>,
+    #[arg(short, long)]
+    base_port: Option<u16>,
+    /// Defaults to the topology name.
+    #[arg(short, long)]
+    suffix: Option<String>,
+    #[arg(long)]
+    scheme: Option<String>,
+    /// tree, pairwise, ring, star, star:<hub> or hierarchical.
+    #[arg(short, long, default_value = "tree")]
+    topology: TopologyKind,
+    #[arg(long, value_enum, default_value = "tcp")]
+    transport: Transport,
+    /// Chunk sizes in KiB, one CSV round each. Overrides the sizes from the config.
+    #[arg(long, value_delimiter = ',')]
+    sizes: Vec<usize>,
+    #[arg(long, default_value_t = 10)]
+    iterations: u32,
+    /// Untimed shares before the timed iterations of each size.
+    #[arg(long, default_value_t = 1)]
+    warmup: u32,
+}
+
+/// Benchmark keys derived from the party id, so no key material has to be distributed.
+/// Real deployments load their own key and the peers' public keys from the config.
+#[cfg(feature = "noise")]
+fn bench_key(id: Id) -> network2::SigningKey {
+    let mut seed = [0; 32];
+    seed[..4].copy_from_slice(&id.to_be_bytes());
+    network2::SigningKey::from_bytes(&seed)
+}
+
+/// Latencies of the timed iterations of one chunk size.
+struct Stats {
+    samples: Vec<Duration>,
+    sorted: Vec<Duration>,
+}
+
+impl Stats {
+    fn new(samples: Vec<Duration>) -> Self {
+        let mut sorted = samples.clone();
+        sorted.sort_unstable();
+        Self { samples, sorted }
+    }
+
+    fn mean(&self) -> Duration {
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    /// Nearest-rank percentile, `p` in `0.0..=1.0`.
+    fn percentile(&self, p: f64) -> Duration {
+        let rank = (p * self.sorted.len() as f64).ceil() as usize;
+        self.sorted[rank.clamp(1, self.sorted.len()) - 1]
+    }
+
+    /// Bytes a party receives per second in a share of `chunk_size` bytes per party.
+    fn bandwidth(&self, chunk_size: usize, party_count: usize) -> f64 {
+        let received = (chunk_size * (party_count - 1)) as f64;
+        received / self.mean().as_secs_f64()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_nanos() as f64 / 1e6
+}
+
+/// Times `iterations` shares per chunk size, after `warmup` untimed ones and a barrier.
+async fn run(
+    collective: impl Collective,
+    chunk_sizes: &[usize],
+    warmup: u32,
+    iterations: u32,
+) -> anyhow::Result<Vec<Stats>> {
+    let id = collective.party_id();
+    let party_count = collective.party_count();
+
+    let mut data = Vec::new();
+    let mut result = Vec::with_capacity(chunk_sizes.len());
+
+    for &chunk_size in chunk_sizes {
+        data.resize(chunk_size * party_count, 0);
+
+        data.chunks_exact_mut(chunk_size)
+            .skip(id as usize)
+            .take(1)
+            .for_each(|part| {
+                let mut rng = rand::rng();
+                rng.fill_bytes(part);
+            });
+
+        for _j in 0..warmup {
+            collective.share(&mut data, chunk_size).await?;
+        }
+        collective.barrier().await?;
+
+        let mut samples = Vec::with_capacity(iterations as usize);
+
+        for _j in 0..iterations {
+            let start_time = quanta::Instant::now();
+            collective.share(&mut data, chunk_size).await?;
+            samples.push(start_time.elapsed());
+        }
+
+        result.push(Stats::new(samples));
+    }
+
+    collective.close().await?;
+
+    Ok(result)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Cli::parse();
+
+    let id = args.id;
+    let kind = args.topology;
+
+    anyhow::ensure!(args.iterations > 0, "--iterations must be positive");
+
+    let suffix = if let Some(suffix) = args.suffix {
+        suffix
+    } else {
+        kind.to_string().replace(':', "")
+    };
+
+    let base_port = args.base_port.unwrap_or(12367);
+
+    let qelect = args.scheme.as_deref() == Some("qelect");
+
+    let (parties, config_sizes) = if let Some(path) = args.config_path {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let parties = Participant::from_reader(&mut reader, base_port)?;
+
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let sizes = line
+            .split_whitespace()
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        (parties, sizes)
+    } else {
+        let Some(party_count) = args.party_count else {
+            anyhow::bail!("either --party-count or --config-path is required");
+        };
+        (
+            Participant::from_default(party_count, base_port)?,
+            vec![600, 200],
+        )
+    };
+
+    let chunk_sizes: Vec<usize> = if !args.sizes.is_empty() {
+        args.sizes.iter().map(|kb| kb * 1024).collect()
+    } else if qelect {
+        vec![1024 * 1024, 1024 * 1024]
+    } else {
+        config_sizes.iter().map(|kb| kb * 1024).collect()
+    };
+
+    anyhow::ensure!(
+        !chunk_sizes.is_empty() && !chunk_sizes.contains(&0),
+        "chunk sizes must be non-empty and positive"
+    );
+
+    let party_count = parties.len();
+
+    let result = match args.transport {
+        Transport::Tcp => {
+            let topology = TcpTopology::new(kind, id, parties).await?;
+            run(topology, &chunk_sizes, args.warmup, args.iterations).await?
+        }
+        #[cfg(feature = "noise")]
+        Transport::Noise => {
+            let mut parties = parties;
+            for party in &mut parties {
+                party.public_key = Some(bench_key(party.id).verifying_key());
+            }
+            let connector =
+                network2::NoiseConnector::new(network2::TcpConnector, &bench_key(id), &parties);
+            let options = network2::SetupOptions::default();
+            let topology =
+                network2::Topology::with_connector(kind, &connector, id, parties, &options).await?;
+            run(topology, &chunk_sizes, args.warmup, args.iterations).await?
+        }
+        #[cfg(feature = "quic")]
+        Transport::Quic => {
+            let connector = network2::QuicConnector::new()?;
+            let options = network2::SetupOptions::default();
+            let topology =
+                network2::Topology::with_connector(kind, &connector, id, parties, &options).await?;
+            run(topology, &chunk_sizes, args.warmup, args.iterations).await?
+        }
+        #[allow(unreachable_patterns)]
+        transport => {
+            anyhow::bail!(
+                "the {} transport needs the `{}` feature",
+                transport.name(),
+                transport.name()
+            )
+        }
+    };
+
+    let transport = args.transport.name();
+    let file = File::create(format!("p{party_count}_id{id}_{transport}_{suffix}.csv"))?;
+
+    let writer = BufWriter::new(file);
+
+    let mut wtr = csv::Writer::from_writer(writer);
+
+    wtr.write_record([
+        "Round",
+        "DataSize_KB",
+        "DataSize_Bytes",
+        "Time_ms",
+        "PartyID",
+        "NumParties",
+        "Min_ms",
+        "Median_ms",
+        "P95_ms",
+        "P99_ms",
+        "Max_ms",
+        "Bandwidth_Bps",
+    ])?;
+    for (i, (&chunk_size, stats)) in chunk_sizes.iter().zip(&result).enumerate() {
+        wtr.serialize((
+            i,
+            chunk_size >> 10,
+            chunk_size,
+            millis(stats.mean()),
+            id,
+            party_count,
+            millis(stats.percentile(0.0)),
+            millis(stats.percentile(0.5)),
+            millis(stats.percentile(0.95)),
+            millis(stats.percentile(0.99)),
+            millis(stats.percentile(1.0)),
+            stats.bandwidth(chunk_size, party_count),
+        ))?;
+    }
+
+    wtr.flush()?;
+
+    let file = File::create(format!(
+        "p{party_count}_id{id}_{transport}_{suffix}_samples.csv"
+    ))?;
+
+    let mut wtr = csv::Writer::from_writer(BufWriter::new(file));
+
+    wtr.write_record([
+        "Round",
+        "Iteration",
+        "DataSize_Bytes",
+        "Time_ms",
+        "PartyID",
+        "NumParties",
+    ])?;
+    for (i, (&chunk_size, stats)) in chunk_sizes.iter().zip(&result).enumerate() {
+        for (j, &sample) in stats.samples.iter().enumerate() {
+            wtr.serialize((i, j, chunk_size, millis(sample), id, party_count))?;
+        }
+    }
+
+    wtr.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    process::{Child, Command},
+    time::Duration,
+};
+
+use clap::Parser;
+use network2::{Id, TopologyKind};
+
+/// How often the launcher checks whether a party has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Runs every party of a local benchmark as a `network2-bench` process and merges
+/// their CSV files into `p{n}_{transport}_{suffix}.csv` and, for the per-iteration
+/// samples, `p{n}_{transport}_{suffix}_samples.csv`.
+#[derive(Parser)]
+struct Cli {
+    #[arg(short, long)]
+    party_count: usize,
+    #[arg(short, long, default_value = "tree")]
+    topology: TopologyKind,
+    #[arg(long, default_value = "tcp")]
+    transport: String,
+    #[arg(short, long, default_value_t = 12367)]
+    base_port: u16,
+    /// Defaults to the topology name.
+    #[arg(short, long)]
+    suffix: Option<String>,
+    #[arg(long)]
+    scheme: Option<String>,
+    #[arg(long, value_delimiter = ',')]
+    sizes: Vec<usize>,
+    #[arg(long)]
+    iterations: Option<u32>,
+    #[arg(long)]
+    warmup: Option<u32>,
+    /// Defaults to the `network2-bench` next to this executable.
+    #[arg(long)]
+    bench: Option<PathBuf>,
+    /// Keeps the per-party CSV files after merging them.
+    #[arg(long)]
+    keep: bool,
+}
+
+fn spawn(args: &Cli, bench: &PathBuf, id: Id, suffix: &str) -> anyhow::Result<Child> {
+    let mut command = Command::new(bench);
+    command
+        .arg("--id")
+        .arg(id.to_string())
+        .arg("--party-count")
+        .arg(args.party_count.to_string())
+        .arg("--base-port")
+        .arg(args.base_port.to_string())
+        .arg("--topology")
+        .arg(args.topology.to_string())
+        .arg("--transport")
+        .arg(&args.transport)
+        .arg("--suffix")
+        .arg(suffix);
+    if let Some(scheme) = &args.scheme {
+        command.arg("--scheme").arg(scheme);
+    }
+    if !args.sizes.is_empty() {
+        let sizes: Vec<String> = args.sizes.iter().map(|size| size.to_string()).collect();
+        command.arg("--sizes").arg(sizes.join(","));
+    }
+    if let Some(iterations) = args.iterations {
+        command.arg("--iterations").arg(iterations.to_string());
+    }
+    if let Some(warmup) = args.warmup {
+        command.arg("--warmup").arg(warmup.to_string());
+    }
+    Ok(command.spawn()?)
+}
+
+fn stop(children: impl IntoIterator<Item = Child>) {
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Waits for every party to exit. As soon as one fails the others are killed, since
+/// they would otherwise wait for it forever.
+fn wait_all(children: Vec<Child>) -> anyhow::Result<()> {
+    let mut running: Vec<(usize, Child)> = children.into_iter().enumerate().collect();
+    let failure = loop {
+        let mut failure = None;
+        running.retain_mut(|(id, child)| match child.try_wait() {
+            Ok(Some(status)) if status.success() => false,
+            Ok(Some(status)) => {
+                failure = Some(anyhow::anyhow!("party {id} failed with {status}"));
+                false
+            }
+            Ok(None) => true,
+            Err(e) => {
+                failure = Some(anyhow::Error::new(e).context(format!("waiting for party {id}")));
+                true
+            }
+        });
+        if failure.is_some() || running.is_empty() {
+            break failure;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    match failure {
+        Some(failure) => {
+            stop(running.into_iter().map(|(_, child)| child));
+            Err(failure.context("stopped the other parties"))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Concatenates the CSV files of all parties into `report`, grouped by round with the
+/// parties in order within each round.
+fn merge(party_files: &[String], report: &str, keep: bool) -> anyhow::Result<()> {
+    let mut headers = None;
+    let mut rows = Vec::new();
+    for path in party_files {
+        let mut reader = csv::Reader::from_path(path)?;
+        headers.get_or_insert(reader.headers()?.clone());
+        for record in reader.records() {
+            rows.push(record?);
+        }
+    }
+
+    let round = |record: &csv::StringRecord| record[0].parse::<usize>().unwrap_or(usize::MAX);
+    rows.sort_by_key(|record| round(record));
+
+    let writer = BufWriter::new(File::create(report)?);
+    let mut wtr = csv::Writer::from_writer(writer);
+    if let Some(headers) = headers {
+        wtr.write_record(&headers)?;
+    }
+    for record in &rows {
+        wtr.write_record(record)?;
+    }
+    wtr.flush()?;
+
+    if !keep {
+        for path in party_files {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    println!("Wrote {report}");
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Cli::parse();
+
+    anyhow::ensure!(args.party_count > 0, "--party-count must be positive");
+
+    let bench = match &args.bench {
+        Some(bench) => bench.clone(),
+        None => std::env::current_exe()?.with_file_name("network2-bench"),
+    };
+
+    let suffix = if let Some(suffix) = &args.suffix {
+        suffix.clone()
+    } else {
+        args.topology.to_string().replace(':', "")
+    };
+
+    let party_count = args.party_count;
+
+    let mut children = Vec::with_capacity(party_count);
+    for id in 0..party_count as Id {
+        match spawn(&args, &bench, id, &suffix) {
+            Ok(child) => children.push(child),
+            Err(e) => {
+                stop(children);
+                return Err(e.context(format!("failed to start {}", bench.display())));
+            }
+        }
+    }
+    wait_all(children)?;
+
+    let transport = &args.transport;
+    for tail in ["", "_samples"] {
+        let party_files: Vec<String> = (0..party_count)
+            .map(|id| format!("p{party_count}_id{id}_{transport}_{suffix}{tail}.csv"))
+            .collect();
+        let report = format!("p{party_count}_{transport}_{suffix}{tail}.csv");
+        merge(&party_files, &report, args.keep)?;
+    }
+
+    Ok(())
+}
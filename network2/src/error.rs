@@ -0,0 +1,62 @@
+use std::fmt;
+
+use crate::Id;
+
+/// Errors raised while setting up or running a topology.
+///
+/// They are returned inside [`anyhow::Error`], so callers can recover the variant
+/// with [`anyhow::Error::downcast_ref`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("gave up connecting to party {peer}")]
+    ConnectTimeout { peer: Id },
+    #[error("party {peer} connected more than once")]
+    DuplicateConnection { peer: Id },
+    #[error("unexpected connection from a peer claiming to be party {claimed_id}")]
+    UnexpectedPeer { claimed_id: Id },
+    #[error("party {peer} failed to authenticate")]
+    HandshakeFailed { peer: Id },
+    #[error("received a message that failed to decrypt")]
+    Decrypt,
+    #[error("still waiting for {pending} peers to connect")]
+    AcceptTimeout { pending: usize },
+    #[error("setup did not finish before the deadline")]
+    SetupTimeout,
+    #[error("party {peer} did not respond in time on {link}")]
+    ShareTimeout { peer: Id, link: Link },
+    #[error("operation was cancelled")]
+    Cancelled,
+    #[error("contributions add up to more than {max} bytes")]
+    LengthLimit { max: usize },
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// The link of a topology that an operation was waiting on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Link {
+    /// A hypercube dimension of a tree.
+    Dimension(u32),
+    /// The link between a folded party and its partner in a tree.
+    Fold,
+    /// A direct link of a full mesh.
+    Mesh,
+    /// A link between neighbours of a ring.
+    Ring,
+    /// A link between the hub of a star and another party.
+    Star,
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Link::Dimension(i) => write!(f, "hypercube dimension {i}"),
+            Link::Fold => f.write_str("fold link"),
+            Link::Mesh => f.write_str("mesh link"),
+            Link::Ring => f.write_str("ring link"),
+            Link::Star => f.write_str("star link"),
+        }
+    }
+}
@@ -1,7 +1,30 @@
+mod error;
 mod net_io;
+mod reduce;
 mod topology;
 
-pub use net_io::{PairWiseNetIO, Role, TcpNetIO, TreeNetIO};
-pub use topology::{Participant, TcpPairWise, TcpTree};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+pub use error::{Error, Link};
+pub use net_io::{
+    Acceptor, Authenticated, AuthenticatedAcceptor, Connector, MemNetIO, NetIO, PairWiseNetIO,
+    Role, TcpAcceptor, TcpConnector, TcpNetIO, TreeNetIO,
+};
+pub use reduce::{AddMod, AddU32, AddU64, Reducer, Xor};
+pub use tokio_util::sync::CancellationToken;
+pub use topology::{
+    Collective, DEFAULT_MAX_GATHERED_LENGTH, DEFAULT_SEGMENT_SIZE, Hierarchical, MemHierarchical,
+    MemPairWise, MemRing, MemStar, MemTree, PairWise, Participant, Ring, RoundStats, SetupOptions,
+    ShareOptions, ShareStats, Star, TcpHierarchical, TcpPairWise, TcpRing, TcpStar, TcpTopology,
+    TcpTree, Topology, TopologyKind, Tree,
+};
+
+#[cfg(feature = "noise")]
+pub use net_io::{NoiseAcceptor, NoiseConnector, NoiseNetIO};
+#[cfg(feature = "quic")]
+pub use net_io::{QuicAcceptor, QuicConnector, QuicNetIO};
+#[cfg(feature = "noise")]
+pub use topology::{NoisePairWise, NoiseTree};
+#[cfg(feature = "quic")]
+pub use topology::{QuicPairWise, QuicTree};
 
 pub type Id = u32;
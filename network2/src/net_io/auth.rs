@@ -0,0 +1,191 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use ed25519_dalek::{SIGNATURE_LENGTH, Signature, Signer, SigningKey, VerifyingKey};
+use rand::RngCore;
+
+use crate::{Error, Id, Participant};
+
+use super::{Acceptor, Connector, PairWiseNetIO, Role};
+
+const DOMAIN: &[u8] = b"network2 handshake v1";
+const NONCE_LENGTH: usize = 32;
+const ACCEPTED: u8 = 1;
+const REJECTED: u8 = 0;
+
+/// Wraps a [`Connector`] with a mutual signed-challenge handshake.
+///
+/// Once the inner transport has exchanged party ids, both ends send a fresh nonce
+/// and sign the transcript with their static key. A link is only handed to the
+/// topology after the peer's signature verifies under the public key of the
+/// participant it claimed to be.
+pub struct Authenticated<T> {
+    inner: T,
+    identity: Arc<Identity>,
+}
+
+/// The listening side of [`Authenticated`].
+pub struct AuthenticatedAcceptor<A> {
+    inner: A,
+    identity: Arc<Identity>,
+}
+
+struct Identity {
+    party_id: Id,
+    signing_key: SigningKey,
+    public_keys: Vec<Option<VerifyingKey>>,
+    handshake_timeout: Duration,
+}
+
+impl<T> Authenticated<T> {
+    /// Authenticates as `party_id` with `signing_key`, checking peers against the
+    /// public keys of `participants`.
+    pub fn new(
+        inner: T,
+        party_id: Id,
+        signing_key: SigningKey,
+        participants: &[Participant],
+    ) -> Self {
+        Self {
+            inner,
+            identity: Arc::new(Identity {
+                party_id,
+                signing_key,
+                public_keys: participants.iter().map(|p| p.public_key).collect(),
+                handshake_timeout: Duration::from_secs(10),
+            }),
+        }
+    }
+
+    /// Sets how long either end may take to complete the handshake.
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        Arc::get_mut(&mut self.identity)
+            .expect("Identity is not shared before binding")
+            .handshake_timeout = handshake_timeout;
+        self
+    }
+}
+
+impl Identity {
+    fn public_key(&self, peer_id: Id) -> Option<&VerifyingKey> {
+        self.public_keys.get(peer_id as usize)?.as_ref()
+    }
+
+    async fn handshake<C: PairWiseNetIO>(
+        &self,
+        net_io: &C,
+        role: Role,
+        peer_id: Id,
+    ) -> anyhow::Result<()> {
+        let peer_key = self
+            .public_key(peer_id)
+            .ok_or_else(|| Error::InvalidConfig(format!("party {peer_id} has no public key")))?;
+
+        let mut nonce = [0; NONCE_LENGTH];
+        rand::rng().fill_bytes(&mut nonce);
+        let mut peer_nonce = [0; NONCE_LENGTH];
+        tokio::try_join!(net_io.send(&nonce), net_io.recv(&mut peer_nonce))?;
+
+        let (client_id, server_id, client_nonce, server_nonce) = match role {
+            Role::Client => (self.party_id, peer_id, &nonce, &peer_nonce),
+            Role::Server => (peer_id, self.party_id, &peer_nonce, &nonce),
+        };
+        let transcript = |signer: u8| {
+            let mut transcript = Vec::with_capacity(DOMAIN.len() + 1 + 8 + 2 * NONCE_LENGTH);
+            transcript.extend_from_slice(DOMAIN);
+            transcript.push(signer);
+            transcript.extend_from_slice(&client_id.to_be_bytes());
+            transcript.extend_from_slice(&server_id.to_be_bytes());
+            transcript.extend_from_slice(client_nonce);
+            transcript.extend_from_slice(server_nonce);
+            transcript
+        };
+        // Distinct tags keep a signature from being reflected back to its signer.
+        let (tag, peer_tag) = match role {
+            Role::Client => (b'C', b'S'),
+            Role::Server => (b'S', b'C'),
+        };
+
+        let signature = self.signing_key.sign(&transcript(tag)).to_bytes();
+        let mut peer_signature = [0; SIGNATURE_LENGTH];
+        tokio::try_join!(net_io.send(&signature), net_io.recv(&mut peer_signature))?;
+
+        let verified = peer_key
+            .verify_strict(
+                &transcript(peer_tag),
+                &Signature::from_bytes(&peer_signature),
+            )
+            .is_ok();
+
+        // The server tells the client whether it accepted it, otherwise a client with a
+        // rejected key would consider the link established, or retry a dropped one.
+        let mut accepted = [if verified { ACCEPTED } else { REJECTED }];
+        match role {
+            Role::Client if !verified => {}
+            Role::Client => net_io.recv(&mut accepted).await?,
+            Role::Server => net_io.send(&accepted).await?,
+        }
+        if !verified || accepted != [ACCEPTED] {
+            return Err(Error::HandshakeFailed { peer: peer_id }.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Connector for Authenticated<T>
+where
+    T: Connector,
+    T::NetIO: PairWiseNetIO,
+{
+    type NetIO = T::NetIO;
+    type Acceptor = AuthenticatedAcceptor<T::Acceptor>;
+
+    async fn bind(&self, address: SocketAddr) -> anyhow::Result<Self::Acceptor> {
+        Ok(AuthenticatedAcceptor {
+            inner: self.inner.bind(address).await?,
+            identity: self.identity.clone(),
+        })
+    }
+
+    async fn connect(
+        &self,
+        party_id: Id,
+        peer_id: Id,
+        peer_address: SocketAddr,
+    ) -> anyhow::Result<Self::NetIO> {
+        let net_io = self.inner.connect(party_id, peer_id, peer_address).await?;
+        // A peer that stalls the handshake is not retried, as it would likely stall again.
+        let handshake = self.identity.handshake(&net_io, Role::Client, peer_id);
+        tokio::time::timeout(self.identity.handshake_timeout, handshake)
+            .await
+            .map_err(|_| Error::HandshakeFailed { peer: peer_id })??;
+        Ok(net_io)
+    }
+}
+
+impl<A> Acceptor for AuthenticatedAcceptor<A>
+where
+    A: Acceptor,
+    A::NetIO: PairWiseNetIO,
+{
+    type NetIO = A::NetIO;
+    type Incoming = A::Incoming;
+
+    async fn incoming(&self) -> anyhow::Result<A::Incoming> {
+        self.inner.incoming().await
+    }
+
+    /// Reads the announced id and runs the handshake, both within the handshake timeout.
+    async fn identify(&self, incoming: A::Incoming) -> anyhow::Result<(Id, Self::NetIO)> {
+        let identify = async {
+            let (peer_id, net_io) = self.inner.identify(incoming).await?;
+            self.identity
+                .handshake(&net_io, Role::Server, peer_id)
+                .await?;
+            Ok((peer_id, net_io))
+        };
+        tokio::time::timeout(self.identity.handshake_timeout, identify)
+            .await
+            .map_err(|_| anyhow::anyhow!("Handshake timed out."))?
+    }
+}
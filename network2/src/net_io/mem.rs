@@ -0,0 +1,83 @@
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, DuplexStream, ReadHalf, WriteHalf},
+    sync::Mutex,
+};
+
+use crate::{PairWiseNetIO, TreeNetIO};
+
+use super::{NetIO, Role};
+
+/// Capacity of each direction of an in-memory pipe.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// An in-process connection backed by a tokio duplex pipe.
+pub struct MemNetIO {
+    role: Role,
+    write_half: Mutex<WriteHalf<DuplexStream>>,
+    read_half: Mutex<ReadHalf<DuplexStream>>,
+}
+
+impl MemNetIO {
+    pub fn new(role: Role, stream: DuplexStream) -> Self {
+        let (read_half, write_half) = tokio::io::split(stream);
+        Self {
+            role,
+            write_half: Mutex::new(write_half),
+            read_half: Mutex::new(read_half),
+        }
+    }
+
+    /// Creates both ends of a connection, returned as `(server, client)`.
+    pub fn pair() -> (Self, Self) {
+        let (server, client) = tokio::io::duplex(PIPE_CAPACITY);
+        (
+            Self::new(Role::Server, server),
+            Self::new(Role::Client, client),
+        )
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+}
+
+impl NetIO for MemNetIO {
+    async fn close(self) -> anyhow::Result<()> {
+        self.write_half.into_inner().shutdown().await?;
+        Ok(())
+    }
+}
+
+impl TreeNetIO for MemNetIO {
+    async fn share(&self, data: &[u8], buf: &mut [u8]) -> anyhow::Result<()> {
+        let send_task = async {
+            let mut write_half_mut = self.write_half.lock().await;
+            write_half_mut.write_all(data).await?;
+            write_half_mut.flush().await?;
+            anyhow::Ok(())
+        };
+
+        let recv_task = async {
+            self.read_half.lock().await.read_exact(buf).await?;
+            anyhow::Ok(())
+        };
+
+        tokio::try_join!(send_task, recv_task)?;
+
+        Ok(())
+    }
+}
+
+impl PairWiseNetIO for MemNetIO {
+    async fn send(&self, data: &[u8]) -> anyhow::Result<()> {
+        let mut write_half_mut = self.write_half.lock().await;
+        write_half_mut.write_all(data).await?;
+        write_half_mut.flush().await?;
+        anyhow::Ok(())
+    }
+
+    async fn recv(&self, data: &mut [u8]) -> anyhow::Result<()> {
+        self.read_half.lock().await.read_exact(data).await?;
+        anyhow::Ok(())
+    }
+}
@@ -1,4 +1,13 @@
-// mod quic;
+use std::net::SocketAddr;
+
+use crate::Id;
+
+mod auth;
+mod mem;
+#[cfg(feature = "noise")]
+mod noise;
+#[cfg(feature = "quic")]
+mod quic;
 mod tcp;
 
 #[derive(Debug, Clone, Copy)]
@@ -7,8 +16,13 @@ pub enum Role {
     Client,
 }
 
+/// A connection to a single peer.
+pub trait NetIO {
+    fn close(self) -> impl std::future::Future<Output = anyhow::Result<()>>;
+}
+
 /// Network IO trait
-pub trait TreeNetIO {
+pub trait TreeNetIO: NetIO {
     fn share(
         &self,
         data: &[u8],
@@ -16,16 +30,57 @@ pub trait TreeNetIO {
     ) -> impl std::future::Future<Output = anyhow::Result<()>>;
 }
 
-pub trait PairWiseNetIO {
-    fn send(self: Arc<Self>, data: &[u8]) -> impl std::future::Future<Output = anyhow::Result<()>>;
+pub trait PairWiseNetIO: NetIO {
+    fn send(&self, data: &[u8]) -> impl std::future::Future<Output = anyhow::Result<()>>;
 
-    fn recv(
-        self: Arc<Self>,
-        data: &mut [u8],
-    ) -> impl std::future::Future<Output = anyhow::Result<()>>;
+    fn recv(&self, data: &mut [u8]) -> impl std::future::Future<Output = anyhow::Result<()>>;
 }
 
-use std::sync::Arc;
+/// Dials peers of a transport and creates the listening side.
+pub trait Connector {
+    type NetIO: NetIO;
+    type Acceptor: Acceptor<NetIO = Self::NetIO>;
+
+    /// Starts listening for peers on `address`.
+    fn bind(
+        &self,
+        address: SocketAddr,
+    ) -> impl std::future::Future<Output = anyhow::Result<Self::Acceptor>>;
+
+    /// Makes a single attempt to reach party `peer_id` at `peer_address`, announcing
+    /// `party_id` to it.
+    fn connect(
+        &self,
+        party_id: Id,
+        peer_id: Id,
+        peer_address: SocketAddr,
+    ) -> impl std::future::Future<Output = anyhow::Result<Self::NetIO>>;
+}
+
+/// The listening side of a [`Connector`].
+///
+/// Accepting a peer is split in two steps so that topologies can identify several
+/// connections at once: a peer that stalls or fails in [`identify`](Acceptor::identify)
+/// then only costs its own connection.
+pub trait Acceptor {
+    type NetIO: NetIO;
+    /// A connection that has not announced its party id yet.
+    type Incoming;
+
+    /// Waits for the next connection.
+    fn incoming(&self) -> impl std::future::Future<Output = anyhow::Result<Self::Incoming>>;
+
+    /// Reads the party id `incoming` announces and completes any handshake on it.
+    fn identify(
+        &self,
+        incoming: Self::Incoming,
+    ) -> impl std::future::Future<Output = anyhow::Result<(Id, Self::NetIO)>>;
+}
 
-// pub use quic::QuicNetIO;
-pub use tcp::TcpNetIO;
+pub use auth::{Authenticated, AuthenticatedAcceptor};
+pub use mem::MemNetIO;
+#[cfg(feature = "noise")]
+pub use noise::{NoiseAcceptor, NoiseConnector, NoiseNetIO};
+#[cfg(feature = "quic")]
+pub use quic::{QuicAcceptor, QuicConnector, QuicNetIO};
+pub use tcp::{TcpAcceptor, TcpConnector, TcpNetIO};
@@ -0,0 +1,271 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use ed25519_dalek::SigningKey;
+use snow::{Builder, StatelessTransportState};
+use tokio::sync::Mutex;
+
+use crate::{Error, Id, PairWiseNetIO, Participant, TreeNetIO};
+
+use super::{Acceptor, Connector, NetIO, Role, TcpAcceptor, TcpConnector, TcpNetIO};
+
+const PATTERN: &str = "Noise_KK_25519_ChaChaPoly_BLAKE2s";
+const TAG_LENGTH: usize = 16;
+/// Largest plaintext carried by a single Noise message.
+const MAX_PAYLOAD: usize = 65535 - TAG_LENGTH;
+/// Each `KK` handshake message is an ephemeral key followed by an empty encrypted payload.
+const HANDSHAKE_LENGTH: usize = 32 + TAG_LENGTH;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An encrypted connection running the Noise transport over another connection.
+///
+/// Every `send` and `share` is sealed as a run of Noise messages of at most 64 KiB,
+/// so each receive has to match a send of the same length on the other end, as all
+/// topologies of this crate already do. A tampered or reordered message fails with
+/// [`Error::Decrypt`].
+pub struct NoiseNetIO<C = TcpNetIO> {
+    inner: C,
+    transport: StatelessTransportState,
+    send_state: Mutex<Direction>,
+    recv_state: Mutex<Direction>,
+}
+
+/// The nonce and ciphertext buffer of one direction of a connection.
+#[derive(Default)]
+struct Direction {
+    nonce: u64,
+    buf: Vec<u8>,
+}
+
+fn sealed_len(len: usize) -> usize {
+    len + len.div_ceil(MAX_PAYLOAD) * TAG_LENGTH
+}
+
+impl<C: PairWiseNetIO> NoiseNetIO<C> {
+    async fn handshake(
+        inner: C,
+        role: Role,
+        peer_id: Id,
+        private_key: &[u8],
+        peer_public_key: &[u8],
+    ) -> anyhow::Result<Self> {
+        let builder = Builder::new(PATTERN.parse()?)
+            .local_private_key(private_key)
+            .remote_public_key(peer_public_key);
+        let mut state = match role {
+            Role::Client => builder.build_initiator()?,
+            Role::Server => builder.build_responder()?,
+        };
+
+        let mut message = [0; HANDSHAKE_LENGTH];
+        // `KK` takes one message in each direction, starting with the initiator.
+        let mut write_next = matches!(role, Role::Client);
+        for _ in 0..2 {
+            if write_next {
+                state.write_message(&[], &mut message)?;
+                inner.send(&message).await?;
+            } else {
+                inner.recv(&mut message).await?;
+                if state.read_message(&message, &mut []).is_err() {
+                    // Answer with a message the initiator cannot read either, so that it
+                    // fails the handshake too instead of retrying a dropped connection.
+                    if matches!(role, Role::Server) {
+                        inner.send(&[0; HANDSHAKE_LENGTH]).await?;
+                    }
+                    return Err(Error::HandshakeFailed { peer: peer_id }.into());
+                }
+            }
+            write_next = !write_next;
+        }
+
+        Ok(Self {
+            inner,
+            transport: state.into_stateless_transport_mode()?,
+            send_state: Mutex::default(),
+            recv_state: Mutex::default(),
+        })
+    }
+}
+
+impl<C> NoiseNetIO<C> {
+    fn seal(&self, direction: &mut Direction, data: &[u8]) -> anyhow::Result<()> {
+        direction.buf.resize(sealed_len(data.len()), 0);
+        for (plain, sealed) in data
+            .chunks(MAX_PAYLOAD)
+            .zip(direction.buf.chunks_mut(MAX_PAYLOAD + TAG_LENGTH))
+        {
+            self.transport
+                .write_message(direction.nonce, plain, sealed)?;
+            direction.nonce += 1;
+        }
+        Ok(())
+    }
+
+    fn open(&self, direction: &mut Direction, buf: &mut [u8]) -> anyhow::Result<()> {
+        for (sealed, plain) in direction
+            .buf
+            .chunks(MAX_PAYLOAD + TAG_LENGTH)
+            .zip(buf.chunks_mut(MAX_PAYLOAD))
+        {
+            self.transport
+                .read_message(direction.nonce, sealed, plain)
+                .map_err(|_| Error::Decrypt)?;
+            direction.nonce += 1;
+        }
+        Ok(())
+    }
+}
+
+impl<C: NetIO> NetIO for NoiseNetIO<C> {
+    async fn close(self) -> anyhow::Result<()> {
+        self.inner.close().await
+    }
+}
+
+impl<C: TreeNetIO> TreeNetIO for NoiseNetIO<C> {
+    async fn share(&self, data: &[u8], buf: &mut [u8]) -> anyhow::Result<()> {
+        let mut send_state = self.send_state.lock().await;
+        let mut recv_state = self.recv_state.lock().await;
+
+        self.seal(&mut send_state, data)?;
+        recv_state.buf.resize(sealed_len(buf.len()), 0);
+        self.inner
+            .share(&send_state.buf, &mut recv_state.buf)
+            .await?;
+        self.open(&mut recv_state, buf)
+    }
+}
+
+impl<C: PairWiseNetIO> PairWiseNetIO for NoiseNetIO<C> {
+    async fn send(&self, data: &[u8]) -> anyhow::Result<()> {
+        let mut send_state = self.send_state.lock().await;
+
+        self.seal(&mut send_state, data)?;
+        self.inner.send(&send_state.buf).await
+    }
+
+    async fn recv(&self, data: &mut [u8]) -> anyhow::Result<()> {
+        let mut recv_state = self.recv_state.lock().await;
+
+        recv_state.buf.resize(sealed_len(data.len()), 0);
+        self.inner.recv(&mut recv_state.buf).await?;
+        self.open(&mut recv_state, data)
+    }
+}
+
+/// Wraps a [`Connector`] so that every link runs a Noise `KK` handshake and is encrypted.
+///
+/// Both ends know each other's static key up front: the X25519 form of the ed25519 keys
+/// in the participant list, so the keys that identify the parties also key the channels.
+pub struct NoiseConnector<T = TcpConnector> {
+    inner: T,
+    keys: Arc<Keys>,
+}
+
+/// The listening side of [`NoiseConnector`].
+pub struct NoiseAcceptor<A = TcpAcceptor> {
+    inner: A,
+    keys: Arc<Keys>,
+}
+
+struct Keys {
+    private_key: [u8; 32],
+    public_keys: Vec<Option<[u8; 32]>>,
+}
+
+impl Keys {
+    fn public_key(&self, peer_id: Id) -> Option<&[u8; 32]> {
+        self.public_keys.get(peer_id as usize)?.as_ref()
+    }
+}
+
+impl<T> NoiseConnector<T> {
+    pub fn new(inner: T, signing_key: &SigningKey, participants: &[Participant]) -> Self {
+        Self {
+            inner,
+            keys: Arc::new(Keys {
+                private_key: signing_key.to_scalar_bytes(),
+                public_keys: participants
+                    .iter()
+                    .map(|p| p.public_key.map(|key| key.to_montgomery().to_bytes()))
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl<T> Connector for NoiseConnector<T>
+where
+    T: Connector,
+    T::NetIO: PairWiseNetIO,
+{
+    type NetIO = NoiseNetIO<T::NetIO>;
+    type Acceptor = NoiseAcceptor<T::Acceptor>;
+
+    async fn bind(&self, address: SocketAddr) -> anyhow::Result<Self::Acceptor> {
+        Ok(NoiseAcceptor {
+            inner: self.inner.bind(address).await?,
+            keys: self.keys.clone(),
+        })
+    }
+
+    async fn connect(
+        &self,
+        party_id: Id,
+        peer_id: Id,
+        peer_address: SocketAddr,
+    ) -> anyhow::Result<Self::NetIO> {
+        let peer_public_key = self
+            .keys
+            .public_key(peer_id)
+            .ok_or_else(|| Error::InvalidConfig(format!("party {peer_id} has no public key")))?;
+
+        let net_io = self.inner.connect(party_id, peer_id, peer_address).await?;
+        let handshake = NoiseNetIO::handshake(
+            net_io,
+            Role::Client,
+            peer_id,
+            &self.keys.private_key,
+            peer_public_key,
+        );
+        // A peer that stalls the handshake is not retried, as it would likely stall again.
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+            .await
+            .map_err(|_| Error::HandshakeFailed { peer: peer_id })?
+    }
+}
+
+impl<A> Acceptor for NoiseAcceptor<A>
+where
+    A: Acceptor,
+    A::NetIO: PairWiseNetIO,
+{
+    type NetIO = NoiseNetIO<A::NetIO>;
+    type Incoming = A::Incoming;
+
+    async fn incoming(&self) -> anyhow::Result<A::Incoming> {
+        self.inner.incoming().await
+    }
+
+    /// Reads the announced id and runs the handshake, both within [`HANDSHAKE_TIMEOUT`].
+    async fn identify(&self, incoming: A::Incoming) -> anyhow::Result<(Id, Self::NetIO)> {
+        let identify = async {
+            let (peer_id, net_io) = self.inner.identify(incoming).await?;
+            let peer_public_key = self.keys.public_key(peer_id).ok_or_else(|| {
+                Error::InvalidConfig(format!("party {peer_id} has no public key"))
+            })?;
+
+            let net_io = NoiseNetIO::handshake(
+                net_io,
+                Role::Server,
+                peer_id,
+                &self.keys.private_key,
+                peer_public_key,
+            )
+            .await?;
+            Ok((peer_id, net_io))
+        };
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, identify)
+            .await
+            .map_err(|_| anyhow::anyhow!("Handshake timed out."))?
+    }
+}
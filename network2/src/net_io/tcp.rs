@@ -1,17 +1,17 @@
-use std::sync::Arc;
+use std::net::SocketAddr;
 
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::{
-        TcpStream,
+        TcpListener, TcpStream,
         tcp::{OwnedReadHalf, OwnedWriteHalf},
     },
     sync::Mutex,
 };
 
-use crate::{PairWiseNetIO, TreeNetIO};
+use crate::{Error, Id, PairWiseNetIO, TreeNetIO};
 
-use super::Role;
+use super::{Acceptor, Connector, NetIO, Role};
 
 pub struct TcpNetIO {
     role: Role,
@@ -32,8 +32,10 @@ impl TcpNetIO {
     pub fn role(&self) -> Role {
         self.role
     }
+}
 
-    pub async fn close(self) -> anyhow::Result<()> {
+impl NetIO for TcpNetIO {
+    async fn close(self) -> anyhow::Result<()> {
         let mut tcp_stream = self
             .write_half
             .into_inner()
@@ -66,15 +68,73 @@ impl TreeNetIO for TcpNetIO {
 }
 
 impl PairWiseNetIO for TcpNetIO {
-    async fn send(self: Arc<Self>, data: &[u8]) -> anyhow::Result<()> {
+    async fn send(&self, data: &[u8]) -> anyhow::Result<()> {
         let mut write_half_mut = self.write_half.lock().await;
         write_half_mut.write_all(data).await?;
         write_half_mut.flush().await?;
         anyhow::Ok(())
     }
 
-    async fn recv(self: Arc<Self>, data: &mut [u8]) -> anyhow::Result<()> {
+    async fn recv(&self, data: &mut [u8]) -> anyhow::Result<()> {
         self.read_half.lock().await.read_exact(data).await?;
         anyhow::Ok(())
     }
 }
+
+/// Plain TCP connections, announcing the party id as a big-endian `u32`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TcpConnector;
+
+pub struct TcpAcceptor(TcpListener);
+
+impl Connector for TcpConnector {
+    type NetIO = TcpNetIO;
+    type Acceptor = TcpAcceptor;
+
+    async fn bind(&self, address: SocketAddr) -> anyhow::Result<TcpAcceptor> {
+        let listener = TcpListener::bind(address).await.map_err(Error::Io)?;
+        Ok(TcpAcceptor(listener))
+    }
+
+    async fn connect(
+        &self,
+        party_id: Id,
+        _peer_id: Id,
+        peer_address: SocketAddr,
+    ) -> anyhow::Result<TcpNetIO> {
+        let tcp_stream = async {
+            let mut tcp_stream = TcpStream::connect(peer_address).await?;
+
+            tcp_stream.set_nodelay(true)?;
+            tcp_stream.write_u32(party_id).await?;
+            tcp_stream.flush().await?;
+
+            std::io::Result::Ok(tcp_stream)
+        }
+        .await
+        .map_err(Error::Io)?;
+
+        Ok(TcpNetIO::new(Role::Client, tcp_stream))
+    }
+}
+
+impl Acceptor for TcpAcceptor {
+    type NetIO = TcpNetIO;
+    type Incoming = TcpStream;
+
+    async fn incoming(&self) -> anyhow::Result<TcpStream> {
+        let (tcp_stream, _addr) = self.0.accept().await.map_err(Error::Io)?;
+        Ok(tcp_stream)
+    }
+
+    async fn identify(&self, mut tcp_stream: TcpStream) -> anyhow::Result<(Id, TcpNetIO)> {
+        let peer_id = async {
+            tcp_stream.set_nodelay(true)?;
+            tcp_stream.read_u32().await
+        }
+        .await
+        .map_err(Error::Io)?;
+
+        Ok((peer_id, TcpNetIO::new(Role::Server, tcp_stream)))
+    }
+}
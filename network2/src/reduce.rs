@@ -0,0 +1,81 @@
+/// Combines the contributions of two parties in a reduction.
+///
+/// The operation must be associative and commutative, as the topologies combine
+/// contributions in whatever order their links deliver them.
+pub trait Reducer {
+    /// Folds `other` into `acc`. Both slices have the same length.
+    fn reduce(&self, acc: &mut [u8], other: &[u8]);
+}
+
+/// Bytewise XOR.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Xor;
+
+impl Reducer for Xor {
+    fn reduce(&self, acc: &mut [u8], other: &[u8]) {
+        for (a, b) in acc.iter_mut().zip(other) {
+            *a ^= b;
+        }
+    }
+}
+
+/// Wrapping addition of little-endian `u32` elements.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AddU32;
+
+impl Reducer for AddU32 {
+    fn reduce(&self, acc: &mut [u8], other: &[u8]) {
+        assert_eq!(acc.len() % 4, 0);
+        for (a, b) in acc.chunks_exact_mut(4).zip(other.chunks_exact(4)) {
+            let sum = u32::from_le_bytes(a.try_into().unwrap())
+                .wrapping_add(u32::from_le_bytes(b.try_into().unwrap()));
+            a.copy_from_slice(&sum.to_le_bytes());
+        }
+    }
+}
+
+/// Wrapping addition of little-endian `u64` elements.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AddU64;
+
+impl Reducer for AddU64 {
+    fn reduce(&self, acc: &mut [u8], other: &[u8]) {
+        assert_eq!(acc.len() % 8, 0);
+        for (a, b) in acc.chunks_exact_mut(8).zip(other.chunks_exact(8)) {
+            let sum = u64::from_le_bytes(a.try_into().unwrap())
+                .wrapping_add(u64::from_le_bytes(b.try_into().unwrap()));
+            a.copy_from_slice(&sum.to_le_bytes());
+        }
+    }
+}
+
+/// Addition modulo `modulus` of little-endian `u64` elements, each already below
+/// `modulus`.
+#[derive(Debug, Clone, Copy)]
+pub struct AddMod {
+    /// Never zero, which [`new`](Self::new) checks.
+    modulus: u64,
+}
+
+impl AddMod {
+    pub fn new(modulus: u64) -> Self {
+        assert_ne!(modulus, 0);
+        Self { modulus }
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.modulus
+    }
+}
+
+impl Reducer for AddMod {
+    fn reduce(&self, acc: &mut [u8], other: &[u8]) {
+        assert_eq!(acc.len() % 8, 0);
+        for (a, b) in acc.chunks_exact_mut(8).zip(other.chunks_exact(8)) {
+            let sum = u64::from_le_bytes(a.try_into().unwrap()) as u128
+                + u64::from_le_bytes(b.try_into().unwrap()) as u128;
+            let sum = (sum % self.modulus as u128) as u64;
+            a.copy_from_slice(&sum.to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Connector, Error, Id, PairWiseNetIO, TreeNetIO};
+
+use super::{Hierarchical, PairWise, Participant, Ring, SetupOptions, ShareOptions, Star, Tree};
+
+/// Operations every topology offers, so protocol code can be written once.
+pub trait Collective {
+    fn party_id(&self) -> Id;
+
+    fn party_count(&self) -> usize;
+
+    /// Allgather: fills every chunk of `data`, of `chunk_size` bytes each, with the
+    /// chunk the party of that index holds.
+    fn share(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+    ) -> impl Future<Output = anyhow::Result<()>> {
+        async move {
+            self.share_with(data, chunk_size, &ShareOptions::default())
+                .await
+        }
+    }
+
+    fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> impl Future<Output = anyhow::Result<()>>;
+
+    /// Sends `data` of `root` to every other party.
+    fn broadcast(&self, root: Id, data: &mut [u8]) -> impl Future<Output = anyhow::Result<()>> {
+        async move {
+            self.broadcast_with(root, data, &ShareOptions::default())
+                .await
+        }
+    }
+
+    fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> impl Future<Output = anyhow::Result<()>>;
+
+    /// Returns once every party has entered the barrier.
+    fn barrier(&self) -> impl Future<Output = anyhow::Result<()>> {
+        async move { self.barrier_with(&ShareOptions::default()).await }
+    }
+
+    fn barrier_with(&self, options: &ShareOptions) -> impl Future<Output = anyhow::Result<()>>;
+
+    fn close(self) -> impl Future<Output = anyhow::Result<()>>;
+}
+
+macro_rules! impl_collective {
+    ($topology:ident, $bound:path) => {
+        impl<C: $bound> Collective for $topology<C> {
+            fn party_id(&self) -> Id {
+                self.party_id
+            }
+
+            fn party_count(&self) -> usize {
+                self.party_count
+            }
+
+            async fn share_with(
+                &self,
+                data: &mut [u8],
+                chunk_size: usize,
+                options: &ShareOptions,
+            ) -> anyhow::Result<()> {
+                $topology::share_with(self, data, chunk_size, options).await
+            }
+
+            async fn broadcast_with(
+                &self,
+                root: Id,
+                data: &mut [u8],
+                options: &ShareOptions,
+            ) -> anyhow::Result<()> {
+                $topology::broadcast_with(self, root, data, options).await
+            }
+
+            async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+                $topology::barrier_with(self, options).await
+            }
+
+            async fn close(self) -> anyhow::Result<()> {
+                $topology::close(self).await
+            }
+        }
+    };
+}
+
+impl_collective!(Tree, TreeNetIO);
+impl_collective!(PairWise, PairWiseNetIO);
+impl_collective!(Ring, PairWiseNetIO);
+impl_collective!(Star, PairWiseNetIO);
+impl_collective!(Hierarchical, TreeNetIO);
+
+/// A topology picked at runtime, e.g. from a command line flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopologyKind {
+    Tree,
+    PairWise,
+    Ring,
+    Star { hub: Id },
+    Hierarchical,
+}
+
+impl FromStr for TopologyKind {
+    type Err = Error;
+
+    /// Parses `tree`, `pairwise`, `ring`, `hierarchical`, or `star`, optionally followed
+    /// by the hub as in `star:3`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidConfig(format!("unknown topology {s}"));
+        match s.split_once(':') {
+            None => match s {
+                "tree" => Ok(Self::Tree),
+                "pairwise" => Ok(Self::PairWise),
+                "ring" => Ok(Self::Ring),
+                "star" => Ok(Self::Star { hub: 0 }),
+                "hierarchical" => Ok(Self::Hierarchical),
+                _ => Err(invalid()),
+            },
+            Some(("star", hub)) => Ok(Self::Star {
+                hub: hub.parse().map_err(|_| invalid())?,
+            }),
+            Some(_) => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for TopologyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tree => f.write_str("tree"),
+            Self::PairWise => f.write_str("pairwise"),
+            Self::Ring => f.write_str("ring"),
+            Self::Star { hub: 0 } => f.write_str("star"),
+            Self::Star { hub } => write!(f, "star:{hub}"),
+            Self::Hierarchical => f.write_str("hierarchical"),
+        }
+    }
+}
+
+/// Any topology over connections of type `C`, chosen by a [`TopologyKind`].
+pub enum Topology<C> {
+    Tree(Tree<C>),
+    PairWise(PairWise<C>),
+    Ring(Ring<C>),
+    Star(Star<C>),
+    Hierarchical(Hierarchical<C>),
+}
+
+impl<C: TreeNetIO + PairWiseNetIO> Topology<C> {
+    /// Establishes a topology of the given `kind` through `connector`.
+    pub async fn with_connector<T>(
+        kind: TopologyKind,
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        Ok(match kind {
+            TopologyKind::Tree => {
+                Self::Tree(Tree::with_connector(connector, party_id, participants, options).await?)
+            }
+            TopologyKind::PairWise => Self::PairWise(
+                PairWise::with_connector(connector, party_id, participants, options).await?,
+            ),
+            TopologyKind::Ring => {
+                Self::Ring(Ring::with_connector(connector, party_id, participants, options).await?)
+            }
+            TopologyKind::Star { hub } => Self::Star(
+                Star::with_connector(connector, party_id, participants, hub, options).await?,
+            ),
+            TopologyKind::Hierarchical => Self::Hierarchical(
+                Hierarchical::with_connector(connector, party_id, participants, options).await?,
+            ),
+        })
+    }
+
+    pub fn kind(&self) -> TopologyKind {
+        match self {
+            Self::Tree(_) => TopologyKind::Tree,
+            Self::PairWise(_) => TopologyKind::PairWise,
+            Self::Ring(_) => TopologyKind::Ring,
+            Self::Star(star) => TopologyKind::Star { hub: star.hub() },
+            Self::Hierarchical(_) => TopologyKind::Hierarchical,
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($self:expr, $topology:ident => $body:expr) => {
+        match $self {
+            Topology::Tree($topology) => $body,
+            Topology::PairWise($topology) => $body,
+            Topology::Ring($topology) => $body,
+            Topology::Star($topology) => $body,
+            Topology::Hierarchical($topology) => $body,
+        }
+    };
+}
+
+impl<C: TreeNetIO + PairWiseNetIO> Collective for Topology<C> {
+    fn party_id(&self) -> Id {
+        dispatch!(self, topology => topology.party_id())
+    }
+
+    fn party_count(&self) -> usize {
+        dispatch!(self, topology => topology.party_count())
+    }
+
+    async fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        dispatch!(self, topology => topology.share_with(data, chunk_size, options).await)
+    }
+
+    async fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        dispatch!(self, topology => topology.broadcast_with(root, data, options).await)
+    }
+
+    async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+        dispatch!(self, topology => topology.barrier_with(options).await)
+    }
+
+    async fn close(self) -> anyhow::Result<()> {
+        dispatch!(self, topology => topology.close().await)
+    }
+}
@@ -0,0 +1,333 @@
+use std::net::SocketAddr;
+
+use crate::{Acceptor, Connector, Error, Id, NetIO, TreeNetIO};
+
+use super::{Participant, SetupOptions, ShareOptions, Tree};
+
+/// Two-level topology for parties spread over a few regions.
+///
+/// Participants sharing a region label form a group, and the lowest id of each group
+/// is its leader. `share` first runs an allgather inside every group, then one among
+/// the leaders across regions, and finally each leader broadcasts the result to its
+/// group, so only the leaders talk across regions.
+///
+/// Both levels are trees. The connector still sees the global ids of the parties, so
+/// keyed connectors find their keys, and each leader listens for the other leaders on
+/// its own port plus the party count.
+pub struct Hierarchical<C> {
+    pub(super) party_id: Id,
+    pub(super) party_count: usize,
+    /// Members of every group, ordered by id; groups are ordered by their leaders.
+    pub(super) groups: Vec<Vec<Id>>,
+    pub(super) group: usize,
+    pub(super) local: Tree<C>,
+    /// The tree among the leaders, held by the leaders only.
+    pub(super) global: Option<Tree<C>>,
+}
+
+/// Groups the parties with the same region, in order of their lowest id.
+pub(super) fn groups<'a>(regions: impl IntoIterator<Item = Option<&'a str>>) -> Vec<Vec<Id>> {
+    let mut labels: Vec<Option<&str>> = Vec::new();
+    let mut groups: Vec<Vec<Id>> = Vec::new();
+    for (id, region) in regions.into_iter().enumerate() {
+        match labels.iter().position(|label| *label == region) {
+            Some(group) => groups[group].push(id as Id),
+            None => {
+                labels.push(region);
+                groups.push(vec![id as Id]);
+            }
+        }
+    }
+    groups
+}
+
+/// Numbers the parties of one level by their index in `ids`, while `inner` sees their
+/// global ids.
+struct Renumbered<'a, T> {
+    inner: &'a T,
+    ids: Vec<Id>,
+}
+
+/// The listening side of [`Renumbered`].
+struct RenumberedAcceptor<A> {
+    inner: A,
+    ids: Vec<Id>,
+}
+
+impl<T: Connector> Connector for Renumbered<'_, T> {
+    type NetIO = T::NetIO;
+    type Acceptor = RenumberedAcceptor<T::Acceptor>;
+
+    async fn bind(&self, address: SocketAddr) -> anyhow::Result<Self::Acceptor> {
+        Ok(RenumberedAcceptor {
+            inner: self.inner.bind(address).await?,
+            ids: self.ids.clone(),
+        })
+    }
+
+    async fn connect(
+        &self,
+        party_id: Id,
+        peer_id: Id,
+        peer_address: SocketAddr,
+    ) -> anyhow::Result<Self::NetIO> {
+        let (party_id, peer_id) = (self.ids[party_id as usize], self.ids[peer_id as usize]);
+        self.inner.connect(party_id, peer_id, peer_address).await
+    }
+}
+
+impl<A: Acceptor> Acceptor for RenumberedAcceptor<A> {
+    type NetIO = A::NetIO;
+    type Incoming = A::Incoming;
+
+    async fn incoming(&self) -> anyhow::Result<A::Incoming> {
+        self.inner.incoming().await
+    }
+
+    async fn identify(&self, incoming: A::Incoming) -> anyhow::Result<(Id, Self::NetIO)> {
+        let (peer_id, net_io) = self.inner.identify(incoming).await?;
+        let index = self
+            .ids
+            .iter()
+            .position(|&id| id == peer_id)
+            .ok_or(Error::UnexpectedPeer {
+                claimed_id: peer_id,
+            })?;
+        Ok((index as Id, net_io))
+    }
+}
+
+impl<C: NetIO> Hierarchical<C> {
+    /// Establishes the group tree of `party_id`, and the leader tree if it leads its
+    /// group, through `connector`.
+    pub async fn with_connector<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        super::check_config(party_id, &participants)?;
+
+        let party_count = participants.len();
+        let groups = groups(participants.iter().map(|p| p.region.as_deref()));
+        let group = groups
+            .iter()
+            .position(|members| members.contains(&party_id))
+            .expect("Every party belongs to a group");
+        let local_id = groups[group]
+            .iter()
+            .position(|&id| id == party_id)
+            .expect("Party is a member of its group") as Id;
+
+        let renumber = |id: Id, index: usize, address: SocketAddr| Participant {
+            id: index as Id,
+            address,
+            ..participants[id as usize].clone()
+        };
+
+        let local_participants = groups[group]
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| renumber(id, index, participants[id as usize].address))
+            .collect();
+
+        let global_task = async {
+            if local_id != 0 {
+                return anyhow::Ok(None);
+            }
+
+            let global_participants = groups
+                .iter()
+                .enumerate()
+                .map(|(index, members)| {
+                    let mut address = participants[members[0] as usize].address;
+                    let port = u16::try_from(party_count)
+                        .ok()
+                        .and_then(|count| address.port().checked_add(count))
+                        .ok_or_else(|| {
+                            Error::InvalidConfig(format!(
+                                "leader port of party {} overflows",
+                                members[0]
+                            ))
+                        })?;
+                    address.set_port(port);
+                    Ok(renumber(members[0], index, address))
+                })
+                .collect::<Result<_, Error>>()?;
+
+            let leaders = Renumbered {
+                inner: connector,
+                ids: groups.iter().map(|members| members[0]).collect(),
+            };
+            let global =
+                Tree::with_connector(&leaders, group as Id, global_participants, options).await?;
+            Ok(Some(global))
+        };
+
+        let members = Renumbered {
+            inner: connector,
+            ids: groups[group].clone(),
+        };
+        let (local, global) = tokio::try_join!(
+            Tree::with_connector(&members, local_id, local_participants, options),
+            global_task
+        )?;
+
+        Ok(Self {
+            party_id,
+            party_count,
+            groups,
+            group,
+            local,
+            global,
+        })
+    }
+
+    /// Members of every group, ordered by id.
+    pub fn groups(&self) -> &[Vec<Id>] {
+        &self.groups
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        self.local.close().await?;
+        if let Some(global) = self.global {
+            global.close().await?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: TreeNetIO> Hierarchical<C> {
+    pub async fn share(&self, data: &mut [u8], chunk_size: usize) -> anyhow::Result<()> {
+        self.share_with(data, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), with `options` bounding each round and the whole
+    /// call across its three steps.
+    pub async fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+        // With empty chunks there is nothing to gather at either level.
+        if chunk_size == 0 {
+            return Ok(());
+        }
+        let timer = options.start();
+
+        let members = &self.groups[self.group];
+        let chunk = |id: Id| chunk_size * id as usize..chunk_size * (id as usize + 1);
+
+        let mut local = vec![0; chunk_size * members.len()];
+        for (index, &id) in members.iter().enumerate() {
+            if id == self.party_id {
+                local[chunk_size * index..chunk_size * (index + 1)]
+                    .copy_from_slice(&data[chunk(id)]);
+            }
+        }
+        self.local
+            .share_with(&mut local, chunk_size, &timer.remaining())
+            .await?;
+
+        // Every chunk, grouped by region in the order of `groups`.
+        let mut grouped = Vec::new();
+        if let Some(global) = &self.global {
+            let lengths: Vec<_> = self
+                .groups
+                .iter()
+                .map(|members| chunk_size * members.len())
+                .collect();
+            // The lengths follow from `chunk_size` rather than from peers, and add up to
+            // the size of `data`, which the caller already holds.
+            let options = ShareOptions {
+                max_gathered_length: data.len(),
+                ..timer.remaining()
+            };
+            global
+                .allgatherv_with(&local, Some(&lengths), &mut grouped, &options)
+                .await?;
+        } else {
+            grouped.resize(data.len(), 0);
+        }
+        self.local
+            .broadcast_with(0, &mut grouped, &timer.remaining())
+            .await?;
+
+        for (id, grouped_chunk) in self
+            .groups
+            .iter()
+            .flatten()
+            .zip(grouped.chunks_exact(chunk_size))
+        {
+            data[chunk(*id)].copy_from_slice(grouped_chunk);
+        }
+
+        Ok(())
+    }
+
+    pub async fn broadcast(&self, root: Id, data: &mut [u8]) -> anyhow::Result<()> {
+        self.broadcast_with(root, data, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends `data` of `root` to its group leader, from there to every other leader,
+    /// and from each leader to its group.
+    pub async fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+        let timer = options.start();
+
+        let (root_group, local_root) = self
+            .groups
+            .iter()
+            .enumerate()
+            .find_map(|(group, members)| {
+                let index = members.iter().position(|&id| id == root)?;
+                Some((group, index as Id))
+            })
+            .expect("Root belongs to a group");
+
+        if root_group == self.group {
+            self.local
+                .broadcast_with(local_root, data, &timer.remaining())
+                .await?;
+        }
+        if let Some(global) = &self.global {
+            global
+                .broadcast_with(root_group as Id, data, &timer.remaining())
+                .await?;
+        }
+        if root_group != self.group {
+            self.local
+                .broadcast_with(0, data, &timer.remaining())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn barrier(&self) -> anyhow::Result<()> {
+        self.barrier_with(&ShareOptions::default()).await
+    }
+
+    /// Returns once every party has entered the barrier: each group gathers at its
+    /// leader, the leaders synchronize, and then release their groups.
+    pub async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+        let timer = options.start();
+        self.local.barrier_with(&timer.remaining()).await?;
+        if let Some(global) = &self.global {
+            global.barrier_with(&timer.remaining()).await?;
+        }
+        self.local.barrier_with(&timer.remaining()).await
+    }
+}
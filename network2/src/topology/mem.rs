@@ -0,0 +1,179 @@
+use crate::{Id, net_io::MemNetIO};
+
+use super::{
+    Hierarchical, PairWise, Ring, Star, Tree, hierarchical::groups, ring::DEFAULT_SEGMENT_SIZE,
+    tree::cube_size,
+};
+
+pub type MemTree = Tree<MemNetIO>;
+pub type MemHierarchical = Hierarchical<MemNetIO>;
+pub type MemPairWise = PairWise<MemNetIO>;
+pub type MemRing = Ring<MemNetIO>;
+pub type MemStar = Star<MemNetIO>;
+
+impl Tree<MemNetIO> {
+    /// Builds all `party_count` parties of a tree, already connected to each other.
+    pub fn local(party_count: usize) -> Vec<Self> {
+        let cube_size = cube_size(party_count);
+        let log_n = cube_size.trailing_zeros();
+
+        let mut connections: Vec<Vec<Option<MemNetIO>>> = (0..party_count)
+            .map(|_| (0..log_n).map(|_| None).collect())
+            .collect();
+        let mut folds: Vec<Option<MemNetIO>> = (0..party_count).map(|_| None).collect();
+
+        for party_id in 0..cube_size {
+            for i in 0..log_n {
+                let peer_id = party_id ^ (1 << i);
+                if peer_id > party_id {
+                    let (server, client) = MemNetIO::pair();
+                    connections[party_id][i as usize] = Some(server);
+                    connections[peer_id][i as usize] = Some(client);
+                }
+            }
+        }
+
+        for peer_id in cube_size..party_count {
+            let (server, client) = MemNetIO::pair();
+            folds[peer_id - cube_size] = Some(server);
+            folds[peer_id] = Some(client);
+        }
+
+        connections
+            .into_iter()
+            .zip(folds)
+            .enumerate()
+            .map(|(party_id, (connections, fold))| Self {
+                party_id: party_id as Id,
+                party_count,
+                log_n,
+                connections: connections.into_iter().flatten().collect(),
+                fold,
+            })
+            .collect()
+    }
+}
+
+impl PairWise<MemNetIO> {
+    /// Builds all `party_count` parties of a full mesh, already connected to each other.
+    pub fn local(party_count: usize) -> Vec<Self> {
+        let mut connections: Vec<Vec<MemNetIO>> = (0..party_count)
+            .map(|_| Vec::with_capacity(party_count - 1))
+            .collect();
+
+        // Filling in ascending peer order keeps every list sorted by peer id.
+        for party_id in 0..party_count {
+            for peer_id in party_id + 1..party_count {
+                let (server, client) = MemNetIO::pair();
+                connections[party_id].push(server);
+                connections[peer_id].push(client);
+            }
+        }
+
+        connections
+            .into_iter()
+            .enumerate()
+            .map(|(party_id, connections)| Self {
+                party_id: party_id as Id,
+                party_count,
+                connections,
+            })
+            .collect()
+    }
+}
+
+impl Ring<MemNetIO> {
+    /// Builds all `party_count` parties of a ring, already connected to each other.
+    pub fn local(party_count: usize) -> Vec<Self> {
+        let mut prevs: Vec<Option<MemNetIO>> = (0..party_count).map(|_| None).collect();
+        let mut nexts: Vec<Option<MemNetIO>> = (0..party_count).map(|_| None).collect();
+
+        if party_count > 1 {
+            for party_id in 0..party_count {
+                let (server, client) = MemNetIO::pair();
+                prevs[(party_id + 1) % party_count] = Some(server);
+                nexts[party_id] = Some(client);
+            }
+        }
+
+        prevs
+            .into_iter()
+            .zip(nexts)
+            .enumerate()
+            .map(|(party_id, (prev, next))| Self {
+                party_id: party_id as Id,
+                party_count,
+                segment_size: DEFAULT_SEGMENT_SIZE,
+                prev,
+                next,
+            })
+            .collect()
+    }
+}
+
+impl Star<MemNetIO> {
+    /// Builds all `party_count` parties of a star around `hub`, already connected to it.
+    pub fn local(party_count: usize, hub: Id) -> Vec<Self> {
+        assert!((hub as usize) < party_count);
+
+        let mut hub_connections = Vec::with_capacity(party_count - 1);
+        let mut connections: Vec<Vec<MemNetIO>> = (0..party_count).map(|_| Vec::new()).collect();
+
+        for party_id in (0..party_count).filter(|&id| id != hub as usize) {
+            let (server, client) = MemNetIO::pair();
+            hub_connections.push(server);
+            connections[party_id].push(client);
+        }
+        connections[hub as usize] = hub_connections;
+
+        connections
+            .into_iter()
+            .enumerate()
+            .map(|(party_id, connections)| Self {
+                party_id: party_id as Id,
+                party_count,
+                hub,
+                connections,
+            })
+            .collect()
+    }
+}
+
+impl Hierarchical<MemNetIO> {
+    /// Builds all parties of a two-level topology, one per entry of `regions`, already
+    /// connected to each other.
+    pub fn local(regions: &[Option<&str>]) -> Vec<Self> {
+        let party_count = regions.len();
+        let groups = groups(regions.iter().copied());
+
+        let mut globals: Vec<Option<Tree<MemNetIO>>> = (0..party_count).map(|_| None).collect();
+        for (members, global) in groups.iter().zip(Tree::local(groups.len())) {
+            globals[members[0] as usize] = Some(global);
+        }
+
+        let mut locals: Vec<Option<(usize, Tree<MemNetIO>)>> =
+            (0..party_count).map(|_| None).collect();
+        for (group, members) in groups.iter().enumerate() {
+            for (&id, local) in members.iter().zip(Tree::local(members.len())) {
+                locals[id as usize] = Some((group, local));
+            }
+        }
+
+        locals
+            .into_iter()
+            .zip(globals)
+            .enumerate()
+            .map(|(party_id, (local, global))| {
+                let (group, local) = local.expect("Every party belongs to a group");
+                Self {
+                    party_id: party_id as Id,
+                    party_count,
+                    groups: groups.clone(),
+                    group,
+                    local,
+                    global,
+                }
+            })
+            .collect()
+    }
+}
@@ -5,24 +5,55 @@ use std::{
     path::Path,
 };
 
-// mod quic;
-mod tcp;
+use ed25519_dalek::{PUBLIC_KEY_LENGTH, VerifyingKey};
+use futures::{StreamExt, stream::FuturesUnordered};
+use tokio::time::{sleep, timeout};
+use tracing::warn;
 
-mod tcp_pair_wise;
+mod collective;
+mod hierarchical;
+mod mem;
+#[cfg(feature = "noise")]
+mod noise;
+mod options;
+mod pair_wise;
+#[cfg(feature = "quic")]
+mod quic;
+mod ring;
+mod star;
+mod stats;
+mod tcp;
+mod tree;
 
-use crate::Id;
+use crate::{Acceptor, Connector, Error, Id};
 
-// pub use quic::QuicTree;
-pub use tcp::TcpTree;
-pub use tcp_pair_wise::TcpPairWise;
+pub use collective::{Collective, Topology, TopologyKind};
+pub use hierarchical::Hierarchical;
+pub use mem::{MemHierarchical, MemPairWise, MemRing, MemStar, MemTree};
+#[cfg(feature = "noise")]
+pub use noise::{NoisePairWise, NoiseTree};
+pub use options::{DEFAULT_MAX_GATHERED_LENGTH, SetupOptions, ShareOptions};
+pub use pair_wise::PairWise;
+#[cfg(feature = "quic")]
+pub use quic::{QuicPairWise, QuicTree};
+pub use ring::{DEFAULT_SEGMENT_SIZE, Ring};
+pub use star::Star;
+pub use stats::{RoundStats, ShareStats};
+pub use tcp::{TcpHierarchical, TcpPairWise, TcpRing, TcpStar, TcpTopology, TcpTree};
+pub use tree::Tree;
 
 /// Represents a participant in the network.
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Participant {
     /// The unique ID of the participant.
     pub id: Id,
     /// The network address of the participant.
     pub address: SocketAddr,
+    /// The static key the participant authenticates with, if any.
+    pub public_key: Option<VerifyingKey>,
+    /// The region the participant runs in, used to group parties in a [`Hierarchical`]
+    /// topology.
+    pub region: Option<String>,
 }
 
 impl Participant {
@@ -33,18 +64,19 @@ impl Participant {
     /// - `base_port`: The starting port number.
     ///
     /// # Returns
-    /// A vector of participants.
-    pub fn from_default(count: usize, base_port: u16) -> Vec<Self> {
-        let count: Id = count.try_into().unwrap();
-        let port = |id| {
-            base_port
-                .checked_add(<Id as TryInto<u16>>::try_into(id).unwrap())
-                .unwrap()
-        };
+    /// A vector of participants, or [`Error::InvalidConfig`] if the ports would overflow.
+    pub fn from_default(count: usize, base_port: u16) -> anyhow::Result<Vec<Self>> {
+        let count: Id = count
+            .try_into()
+            .map_err(|_| Error::InvalidConfig(format!("{count} parties is too many")))?;
         (0..count)
-            .map(|id| Participant {
-                id,
-                address: SocketAddr::from(([127, 0, 0, 1], port(id))),
+            .map(|id| {
+                Ok(Participant {
+                    id,
+                    address: SocketAddr::from(([127, 0, 0, 1], port(base_port, id)?)),
+                    public_key: None,
+                    region: None,
+                })
             })
             .collect()
     }
@@ -65,22 +97,29 @@ impl Participant {
 
         let mut parties = Vec::with_capacity(party_count);
 
-        let port = |id| {
-            base_port
-                .checked_add(<Id as TryInto<u16>>::try_into(id).unwrap())
-                .unwrap()
-        };
-
         for i in 0..party_count {
             line.clear();
             reader.read_line(&mut line)?;
-            let addr = trim_end(&mut line).parse::<Ipv4Addr>()?;
+            // Each line holds an address, optionally followed by a hex-encoded public key
+            // and a `region=<label>`, in any order.
+            let mut fields = trim_end(&mut line).split_whitespace();
+            let addr = fields.next().unwrap_or_default().parse::<Ipv4Addr>()?;
+            let mut public_key = None;
+            let mut region = None;
+            for field in fields {
+                match field.strip_prefix("region=") {
+                    Some(label) => region = Some(label.to_owned()),
+                    None => public_key = Some(parse_public_key(field)?),
+                }
+            }
 
             let id = i as Id;
 
             parties.push(Participant {
                 id,
-                address: SocketAddr::from((addr, port(id))),
+                address: SocketAddr::from((addr, port(base_port, id)?)),
+                public_key,
+                region,
             });
         }
 
@@ -88,6 +127,161 @@ impl Participant {
     }
 }
 
+fn parse_public_key(hex: &str) -> Result<VerifyingKey, Error> {
+    let invalid = || Error::InvalidConfig(format!("invalid public key {hex}"));
+
+    let mut bytes = [0; PUBLIC_KEY_LENGTH];
+    if hex.len() != 2 * bytes.len() || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let digits = std::str::from_utf8(digits).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(digits, 16).map_err(|_| invalid())?;
+    }
+
+    VerifyingKey::from_bytes(&bytes).map_err(|_| invalid())
+}
+
+/// Port of party `id`, counting up from `base_port`.
+fn port(base_port: u16, id: Id) -> Result<u16, Error> {
+    u16::try_from(id)
+        .ok()
+        .and_then(|id| base_port.checked_add(id))
+        .ok_or_else(|| Error::InvalidConfig(format!("port of party {id} overflows")))
+}
+
+/// Checks that `party_id` is one of `participants`, which must be numbered in order.
+fn check_config(party_id: Id, participants: &[Participant]) -> Result<(), Error> {
+    if let Some((i, p)) = participants
+        .iter()
+        .enumerate()
+        .find(|(i, p)| p.id as usize != *i)
+    {
+        return Err(Error::InvalidConfig(format!(
+            "participant {i} has id {}",
+            p.id
+        )));
+    }
+    if party_id as usize >= participants.len() {
+        return Err(Error::InvalidConfig(format!(
+            "party {party_id} is not among {} participants",
+            participants.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Dials `peer_address` until it accepts, backing off between attempts.
+///
+/// A peer that is reached but rejects the handshake, or a missing key, fails right away:
+/// retrying cannot fix either.
+async fn connect<T: Connector>(
+    connector: &T,
+    party_id: Id,
+    peer_id: Id,
+    peer_address: SocketAddr,
+    options: &SetupOptions,
+) -> anyhow::Result<T::NetIO> {
+    let mut attempt = 0;
+    loop {
+        match connector.connect(party_id, peer_id, peer_address).await {
+            Ok(net_io) => break Ok(net_io),
+            Err(error) => {
+                if let Some(Error::HandshakeFailed { .. } | Error::InvalidConfig(_)) =
+                    error.downcast_ref()
+                {
+                    return Err(error);
+                }
+            }
+        }
+        if attempt == options.max_retries {
+            return Err(Error::ConnectTimeout { peer: peer_id }.into());
+        }
+        sleep(options.backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Accepts `client_count` distinct peers for which `expected` is true.
+///
+/// Connections are identified concurrently, and any that fails to, claims an unexpected
+/// id or one already accepted is logged and dropped, so a stray connection cannot stall
+/// setup. Only an authenticating connector keeps it from claiming a party's id first.
+async fn accept<A: Acceptor>(
+    acceptor: &A,
+    client_count: usize,
+    options: &SetupOptions,
+    expected: impl Fn(Id) -> bool,
+) -> anyhow::Result<Vec<(Id, A::NetIO)>> {
+    let mut accepted: Vec<(Id, A::NetIO)> = Vec::with_capacity(client_count);
+    let accept_all = async {
+        let mut identifying = FuturesUnordered::new();
+        while accepted.len() != client_count {
+            tokio::select! {
+                incoming = acceptor.incoming() => identifying.push(acceptor.identify(incoming?)),
+                Some(identified) = identifying.next() => match identified {
+                    Ok((peer_id, _)) if !expected(peer_id) => {
+                        warn!("{}", Error::UnexpectedPeer { claimed_id: peer_id });
+                    }
+                    Ok((peer_id, _)) if accepted.iter().any(|(id, _)| *id == peer_id) => {
+                        warn!("{}", Error::DuplicateConnection { peer: peer_id });
+                    }
+                    Ok(peer) => accepted.push(peer),
+                    Err(error) => warn!("Dropped an incoming connection: {error:#}"),
+                },
+            }
+        }
+        anyhow::Ok(())
+    };
+
+    match options.accept_timeout {
+        Some(accept_timeout) => {
+            timeout(accept_timeout, accept_all)
+                .await
+                .map_err(|_| Error::AcceptTimeout {
+                    pending: client_count - accepted.len(),
+                })??
+        }
+        None => accept_all.await?,
+    }
+    Ok(accepted)
+}
+
+/// Size of a length as exchanged by `allgatherv`: a little-endian `u64`.
+const LENGTH_SIZE: usize = 8;
+
+fn decode_lengths(buf: &[u8], max: usize) -> Result<Vec<usize>, Error> {
+    buf.chunks_exact(LENGTH_SIZE)
+        .map(|length| {
+            usize::try_from(u64::from_le_bytes(length.try_into().unwrap()))
+                .map_err(|_| Error::LengthLimit { max })
+        })
+        .collect()
+}
+
+/// Prefix sums of `lengths`, after checking that they agree with the own contribution
+/// and add up to at most `max`.
+fn offsets(
+    lengths: &[usize],
+    party_id: Id,
+    own_length: usize,
+    max: usize,
+) -> Result<Vec<usize>, Error> {
+    assert_eq!(lengths[party_id as usize], own_length);
+
+    let mut offsets = Vec::with_capacity(lengths.len() + 1);
+    let mut offset: usize = 0;
+    offsets.push(offset);
+    for length in lengths {
+        offset = offset
+            .checked_add(*length)
+            .filter(|&offset| offset <= max)
+            .ok_or(Error::LengthLimit { max })?;
+        offsets.push(offset);
+    }
+    Ok(offsets)
+}
+
 fn trim_end(buf: &mut String) -> &str {
     if buf.ends_with('\n') {
         buf.pop();
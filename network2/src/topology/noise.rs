@@ -0,0 +1,60 @@
+use ed25519_dalek::SigningKey;
+
+use crate::{Id, NoiseConnector, TcpConnector, net_io::NoiseNetIO};
+
+use super::{PairWise, Participant, SetupOptions, Tree};
+
+pub type NoiseTree = Tree<NoiseNetIO>;
+pub type NoisePairWise = PairWise<NoiseNetIO>;
+
+impl Tree<NoiseNetIO> {
+    pub async fn new(
+        party_id: Id,
+        participants: Vec<Participant>,
+        signing_key: &SigningKey,
+    ) -> anyhow::Result<Self> {
+        Self::with_options(
+            party_id,
+            participants,
+            signing_key,
+            &SetupOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn with_options(
+        party_id: Id,
+        participants: Vec<Participant>,
+        signing_key: &SigningKey,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self> {
+        let connector = NoiseConnector::new(TcpConnector, signing_key, &participants);
+        Self::with_connector(&connector, party_id, participants, options).await
+    }
+}
+
+impl PairWise<NoiseNetIO> {
+    pub async fn new(
+        party_id: Id,
+        participants: Vec<Participant>,
+        signing_key: &SigningKey,
+    ) -> anyhow::Result<Self> {
+        Self::with_options(
+            party_id,
+            participants,
+            signing_key,
+            &SetupOptions::default(),
+        )
+        .await
+    }
+
+    pub async fn with_options(
+        party_id: Id,
+        participants: Vec<Participant>,
+        signing_key: &SigningKey,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self> {
+        let connector = NoiseConnector::new(TcpConnector, signing_key, &participants);
+        Self::with_connector(&connector, party_id, participants, options).await
+    }
+}
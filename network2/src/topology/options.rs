@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::time::{Instant, timeout_at};
+use tokio_util::sync::CancellationToken;
+
+use crate::{Error, Id, Link};
+
+/// Largest total an `allgatherv` gathers unless [`ShareOptions`] says otherwise.
+pub const DEFAULT_MAX_GATHERED_LENGTH: usize = 256 * 1024 * 1024;
+
+/// Controls how long and how persistently a topology waits for its peers during setup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetupOptions {
+    /// How many times a failed connection attempt is retried before giving up.
+    pub max_retries: u32,
+    /// Delay before the first retry; it doubles after every failed attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Randomizes each delay within `[backoff / 2, backoff]` so parties do not retry in lockstep.
+    pub jitter: bool,
+    /// Bound on the whole setup, including binding, dialing and accepting.
+    pub deadline: Option<Duration>,
+    /// Bound on waiting for the peers that are expected to dial this party.
+    pub accept_timeout: Option<Duration>,
+}
+
+impl Default for SetupOptions {
+    fn default() -> Self {
+        Self {
+            max_retries: 100,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            jitter: true,
+            deadline: None,
+            accept_timeout: None,
+        }
+    }
+}
+
+impl SetupOptions {
+    /// Delay to wait after `attempt` failed connection attempts.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << attempt.min(31))
+            .min(self.max_backoff);
+        if self.jitter && !backoff.is_zero() {
+            rand::rng().random_range(backoff / 2..=backoff)
+        } else {
+            backoff
+        }
+    }
+
+    /// Runs a setup future under the configured deadline.
+    pub(super) async fn within_deadline<R>(
+        &self,
+        setup: impl Future<Output = anyhow::Result<R>>,
+    ) -> anyhow::Result<R> {
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, setup)
+                .await
+                .map_err(|_| Error::SetupTimeout)?,
+            None => setup.await,
+        }
+    }
+}
+
+/// Bounds on a single collective operation such as `share`.
+#[derive(Debug, Clone)]
+pub struct ShareOptions {
+    /// Bound on each round, i.e. one hypercube dimension or one fold step of a tree,
+    /// the whole exchange of a mesh, one segment on a ring link, or one of the gather and
+    /// relay steps of a star.
+    pub round_timeout: Option<Duration>,
+    /// Bound on the whole operation.
+    pub timeout: Option<Duration>,
+    /// Aborts the operation with [`Error::Cancelled`] once cancelled.
+    pub cancel: Option<CancellationToken>,
+    /// Largest total an `allgatherv` gathers. Peers announce the lengths of their own
+    /// contributions, so this bounds how much memory they can make this party allocate.
+    pub max_gathered_length: usize,
+}
+
+impl Default for ShareOptions {
+    fn default() -> Self {
+        Self {
+            round_timeout: None,
+            timeout: None,
+            cancel: None,
+            max_gathered_length: DEFAULT_MAX_GATHERED_LENGTH,
+        }
+    }
+}
+
+impl ShareOptions {
+    pub(super) fn start(&self) -> ShareTimer<'_> {
+        ShareTimer {
+            options: self,
+            deadline: self.timeout.map(|t| Instant::now() + t),
+        }
+    }
+}
+
+/// Tracks the deadlines of one running operation.
+pub(super) struct ShareTimer<'a> {
+    options: &'a ShareOptions,
+    deadline: Option<Instant>,
+}
+
+impl ShareTimer<'_> {
+    /// Deadline of a round starting now.
+    pub(super) fn round(&self) -> Option<Instant> {
+        let round = self.options.round_timeout.map(|t| Instant::now() + t);
+        match (round, self.deadline) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Options for one step of an operation made of several: the same bounds, with the
+    /// total timeout cut down to what is left of this operation's.
+    pub(super) fn remaining(&self) -> ShareOptions {
+        ShareOptions {
+            timeout: self
+                .deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now())),
+            ..self.options.clone()
+        }
+    }
+
+    /// Runs one transfer with `peer` over `link`, bounded by `deadline` and the cancel token.
+    pub(super) async fn run(
+        &self,
+        deadline: Option<Instant>,
+        peer: Id,
+        link: Link,
+        transfer: impl Future<Output = anyhow::Result<()>>,
+    ) -> anyhow::Result<()> {
+        let timed = async {
+            match deadline {
+                Some(deadline) => timeout_at(deadline, transfer)
+                    .await
+                    .map_err(|_| Error::ShareTimeout { peer, link })?,
+                None => transfer.await,
+            }
+        };
+
+        match &self.options.cancel {
+            Some(cancel) => tokio::select! {
+                biased;
+                _ = cancel.cancelled() => Err(Error::Cancelled.into()),
+                result = timed => result,
+            },
+            None => timed.await,
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use futures::future::try_join_all;
+
+use crate::{Connector, Error, Id, Link, NetIO, PairWiseNetIO};
+
+use super::{
+    LENGTH_SIZE, Participant, SetupOptions, ShareOptions, accept, check_config, connect,
+    decode_lengths, offsets,
+};
+
+/// Full mesh where every party exchanges its chunk with every other party directly.
+pub struct PairWise<C> {
+    pub(super) party_id: Id,
+    pub(super) party_count: usize,
+    pub(super) connections: Vec<C>,
+}
+
+impl<C: NetIO> PairWise<C> {
+    /// Establishes a link from `party_id` to every other participant through `connector`.
+    pub async fn with_connector<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        options
+            .within_deadline(Self::establish(connector, party_id, participants, options))
+            .await
+    }
+
+    async fn establish<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        check_config(party_id, &participants)?;
+
+        let party_count = participants.len();
+
+        let acceptor = connector
+            .bind(participants[party_id as usize].address)
+            .await?;
+
+        // Only higher parties dial us.
+        let client_count = party_count - party_id as usize - 1;
+        let listen_task = accept(&acceptor, client_count, options, |peer_id| {
+            peer_id > party_id && (peer_id as usize) < party_count
+        });
+
+        let connect_task = async {
+            let mut connected = Vec::with_capacity(party_id as usize);
+            for peer_id in 0..party_id {
+                let peer_address = participants[peer_id as usize].address;
+                connected.push((
+                    peer_id,
+                    connect(connector, party_id, peer_id, peer_address, options).await?,
+                ));
+            }
+            anyhow::Ok(connected)
+        };
+
+        let (mut connections, mut accepted) = tokio::try_join!(connect_task, listen_task)?;
+        connections.append(&mut accepted);
+
+        connections.sort_unstable_by_key(|a| a.0);
+
+        if let Some(pair) = connections.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(Error::DuplicateConnection { peer: pair[0].0 }.into());
+        }
+
+        let connections = connections.into_iter().map(|(_, net_io)| net_io).collect();
+
+        Ok(Self {
+            party_id,
+            party_count,
+            connections,
+        })
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        for c in self.connections {
+            c.close().await?
+        }
+        Ok(())
+    }
+}
+
+impl<C: PairWiseNetIO> PairWise<C> {
+    pub async fn share(&self, data: &mut [u8], chunk_size: usize) -> anyhow::Result<()> {
+        self.share_with(data, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), bounded by the timeouts and cancel token in `options`.
+    ///
+    /// The whole exchange counts as a single round.
+    pub async fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+
+        // With empty chunks there is nothing to move.
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let deadline = timer.round();
+
+        let (recv_chunks1, others) = data.split_at_mut(chunk_size * (self.party_id as usize));
+        let (send_chunk, recv_chunks2) = others.split_at_mut(chunk_size);
+        let send_chunk = &*send_chunk;
+
+        let send_tasks = self
+            .peers()
+            .map(|(peer, conn)| timer.run(deadline, peer, Link::Mesh, conn.send(send_chunk)));
+
+        let recv_tasks = self
+            .peers()
+            .zip(
+                recv_chunks1
+                    .chunks_exact_mut(chunk_size)
+                    .chain(recv_chunks2.chunks_exact_mut(chunk_size)),
+            )
+            .map(|((peer, conn), recv_chunk)| {
+                timer.run(deadline, peer, Link::Mesh, conn.recv(recv_chunk))
+            });
+
+        // Drive every link from this task so `data` only needs to outlive the call.
+        tokio::try_join!(try_join_all(send_tasks), try_join_all(recv_tasks))?;
+
+        Ok(())
+    }
+
+    pub async fn broadcast(&self, root: Id, data: &mut [u8]) -> anyhow::Result<()> {
+        self.broadcast_with(root, data, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends `data` of `root` directly to every other party.
+    pub async fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let timer = options.start();
+        let deadline = timer.round();
+
+        if root == self.party_id {
+            let data = &*data;
+            let send_tasks = self
+                .peers()
+                .map(|(peer, conn)| timer.run(deadline, peer, Link::Mesh, conn.send(data)));
+            try_join_all(send_tasks).await?;
+        } else {
+            let conn = self.connection(root);
+            timer
+                .run(deadline, root, Link::Mesh, conn.recv(data))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn gather(
+        &self,
+        root: Id,
+        my_chunk: &[u8],
+        out: Option<&mut [u8]>,
+    ) -> anyhow::Result<()> {
+        self.gather_with(root, my_chunk, out, &ShareOptions::default())
+            .await
+    }
+
+    /// Collects every party's `my_chunk` into `out` of `root`, ordered by party id.
+    ///
+    /// Only `root` passes `out`, of `my_chunk.len()` bytes per party.
+    pub async fn gather_with(
+        &self,
+        root: Id,
+        my_chunk: &[u8],
+        out: Option<&mut [u8]>,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let chunk_size = my_chunk.len();
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let deadline = timer.round();
+
+        if root != self.party_id {
+            let conn = self.connection(root);
+            return timer
+                .run(deadline, root, Link::Mesh, conn.send(my_chunk))
+                .await;
+        }
+
+        let out = out.expect("Root must provide the output buffer!");
+        assert_eq!(out.len(), chunk_size * self.party_count);
+
+        let (recv_chunks1, others) = out.split_at_mut(chunk_size * (self.party_id as usize));
+        let (own, recv_chunks2) = others.split_at_mut(chunk_size);
+        own.copy_from_slice(my_chunk);
+
+        let recv_tasks = self
+            .peers()
+            .zip(
+                recv_chunks1
+                    .chunks_exact_mut(chunk_size)
+                    .chain(recv_chunks2.chunks_exact_mut(chunk_size)),
+            )
+            .map(|((peer, conn), recv_chunk)| {
+                timer.run(deadline, peer, Link::Mesh, conn.recv(recv_chunk))
+            });
+        try_join_all(recv_tasks).await?;
+
+        Ok(())
+    }
+
+    pub async fn scatter(
+        &self,
+        root: Id,
+        input: Option<&[u8]>,
+        my_chunk: &mut [u8],
+    ) -> anyhow::Result<()> {
+        self.scatter_with(root, input, my_chunk, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends chunk `i` of `input` of `root` directly to party `i` as its `my_chunk`.
+    ///
+    /// Only `root` passes `input`.
+    pub async fn scatter_with(
+        &self,
+        root: Id,
+        input: Option<&[u8]>,
+        my_chunk: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let chunk_size = my_chunk.len();
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let deadline = timer.round();
+
+        if root != self.party_id {
+            let conn = self.connection(root);
+            return timer
+                .run(deadline, root, Link::Mesh, conn.recv(my_chunk))
+                .await;
+        }
+
+        let input = input.expect("Root must provide the input buffer!");
+        assert_eq!(input.len(), chunk_size * self.party_count);
+
+        let (send_chunks1, others) = input.split_at(chunk_size * (self.party_id as usize));
+        let (own, send_chunks2) = others.split_at(chunk_size);
+        my_chunk.copy_from_slice(own);
+
+        let send_tasks = self
+            .peers()
+            .zip(
+                send_chunks1
+                    .chunks_exact(chunk_size)
+                    .chain(send_chunks2.chunks_exact(chunk_size)),
+            )
+            .map(|((peer, conn), send_chunk)| {
+                timer.run(deadline, peer, Link::Mesh, conn.send(send_chunk))
+            });
+        try_join_all(send_tasks).await?;
+
+        Ok(())
+    }
+
+    pub async fn all_to_all(
+        &self,
+        send: &[u8],
+        recv: &mut [u8],
+        chunk_size: usize,
+    ) -> anyhow::Result<()> {
+        self.all_to_all_with(send, recv, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends chunk `j` of `send` directly to party `j`, and fills chunk `i` of `recv`
+    /// with the chunk party `i` addressed to this party.
+    pub async fn all_to_all_with(
+        &self,
+        send: &[u8],
+        recv: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(send.len(), chunk_size * self.party_count);
+        assert_eq!(recv.len(), chunk_size * self.party_count);
+
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let deadline = timer.round();
+
+        let (send_chunks1, others) = send.split_at(chunk_size * (self.party_id as usize));
+        let (own, send_chunks2) = others.split_at(chunk_size);
+        let (recv_chunks1, others) = recv.split_at_mut(chunk_size * (self.party_id as usize));
+        let (own_buf, recv_chunks2) = others.split_at_mut(chunk_size);
+        own_buf.copy_from_slice(own);
+
+        let send_tasks = self
+            .peers()
+            .zip(
+                send_chunks1
+                    .chunks_exact(chunk_size)
+                    .chain(send_chunks2.chunks_exact(chunk_size)),
+            )
+            .map(|((peer, conn), send_chunk)| {
+                timer.run(deadline, peer, Link::Mesh, conn.send(send_chunk))
+            });
+
+        let recv_tasks = self
+            .peers()
+            .zip(
+                recv_chunks1
+                    .chunks_exact_mut(chunk_size)
+                    .chain(recv_chunks2.chunks_exact_mut(chunk_size)),
+            )
+            .map(|((peer, conn), recv_chunk)| {
+                timer.run(deadline, peer, Link::Mesh, conn.recv(recv_chunk))
+            });
+
+        tokio::try_join!(try_join_all(send_tasks), try_join_all(recv_tasks))?;
+
+        Ok(())
+    }
+
+    pub async fn allgatherv(
+        &self,
+        data: &[u8],
+        lengths: Option<&[usize]>,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<Vec<usize>> {
+        self.allgatherv_with(data, lengths, out, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), but every party contributes a `data` of its own length.
+    ///
+    /// Party `i`'s contribution ends up at `out[offsets[i]..offsets[i + 1]]`, where
+    /// `offsets` is the returned table. If the parties already know every length, they
+    /// pass them as `lengths` and skip the round that exchanges them.
+    ///
+    /// Fails with [`Error::LengthLimit`], before allocating `out`, if the lengths add up
+    /// to more than [`ShareOptions::max_gathered_length`].
+    pub async fn allgatherv_with(
+        &self,
+        data: &[u8],
+        lengths: Option<&[usize]>,
+        out: &mut Vec<u8>,
+        options: &ShareOptions,
+    ) -> anyhow::Result<Vec<usize>> {
+        let timer = options.start();
+        let lengths = match lengths {
+            Some(lengths) => lengths.to_vec(),
+            None => {
+                let mut buf = vec![0; LENGTH_SIZE * self.party_count];
+                let own = LENGTH_SIZE * self.party_id as usize;
+                buf[own..own + LENGTH_SIZE].copy_from_slice(&(data.len() as u64).to_le_bytes());
+                self.share_with(&mut buf, LENGTH_SIZE, &timer.remaining())
+                    .await?;
+                decode_lengths(&buf, options.max_gathered_length)?
+            }
+        };
+        assert_eq!(lengths.len(), self.party_count);
+        let offsets = offsets(
+            &lengths,
+            self.party_id,
+            data.len(),
+            options.max_gathered_length,
+        )?;
+
+        out.resize(offsets[self.party_count], 0);
+
+        let deadline = timer.round();
+
+        let mut rest = &mut out[..];
+        let mut recv_parts = Vec::with_capacity(self.party_count - 1);
+        for (i, length) in lengths.iter().enumerate() {
+            let (part, tail) = rest.split_at_mut(*length);
+            if i == self.party_id as usize {
+                part.copy_from_slice(data);
+            } else {
+                recv_parts.push(part);
+            }
+            rest = tail;
+        }
+
+        let send_tasks = self
+            .peers()
+            .map(|(peer, conn)| timer.run(deadline, peer, Link::Mesh, conn.send(data)));
+
+        let recv_tasks = self
+            .peers()
+            .zip(recv_parts)
+            .map(|((peer, conn), part)| timer.run(deadline, peer, Link::Mesh, conn.recv(part)));
+
+        tokio::try_join!(try_join_all(send_tasks), try_join_all(recv_tasks))?;
+
+        Ok(offsets)
+    }
+
+    pub async fn barrier(&self) -> anyhow::Result<()> {
+        self.barrier_with(&ShareOptions::default()).await
+    }
+
+    /// Returns once every party has entered the barrier, by swapping a token with every
+    /// peer.
+    pub async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+        let timer = options.start();
+        let deadline = timer.round();
+
+        let send_tasks = self
+            .peers()
+            .map(|(peer, conn)| timer.run(deadline, peer, Link::Mesh, conn.send(&[0])));
+
+        let mut tokens = vec![[0]; self.connections.len()];
+        let recv_tasks = self
+            .peers()
+            .zip(&mut tokens)
+            .map(|((peer, conn), token)| timer.run(deadline, peer, Link::Mesh, conn.recv(token)));
+
+        tokio::try_join!(try_join_all(send_tasks), try_join_all(recv_tasks))?;
+
+        Ok(())
+    }
+
+    /// Connections paired with the id of the party at the other end.
+    fn peers(&self) -> impl Iterator<Item = (Id, &C)> {
+        self.connections.iter().enumerate().map(|(i, conn)| {
+            let peer = if i < self.party_id as usize { i } else { i + 1 };
+            (peer as Id, conn)
+        })
+    }
+
+    /// Connection to `peer`, which must not be this party.
+    fn connection(&self, peer: Id) -> &C {
+        let index = if peer < self.party_id { peer } else { peer - 1 };
+        &self.connections[index as usize]
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{Id, QuicConnector, net_io::QuicNetIO};
+
+use super::{PairWise, Participant, SetupOptions, Tree};
+
+pub type QuicTree = Tree<QuicNetIO>;
+pub type QuicPairWise = PairWise<QuicNetIO>;
+
+impl Tree<QuicNetIO> {
+    pub async fn new(party_id: Id, participants: Vec<Participant>) -> anyhow::Result<Self> {
+        Self::with_options(party_id, participants, &SetupOptions::default()).await
+    }
+
+    pub async fn with_options(
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self> {
+        Self::with_connector(&QuicConnector::new()?, party_id, participants, options).await
+    }
+}
+
+impl PairWise<QuicNetIO> {
+    pub async fn new(party_id: Id, participants: Vec<Participant>) -> anyhow::Result<Self> {
+        Self::with_options(party_id, participants, &SetupOptions::default()).await
+    }
+
+    pub async fn with_options(
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self> {
+        Self::with_connector(&QuicConnector::new()?, party_id, participants, options).await
+    }
+}
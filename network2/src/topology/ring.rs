@@ -0,0 +1,265 @@
+use tokio::sync::mpsc;
+
+use crate::{Connector, Id, Link, NetIO, PairWiseNetIO};
+
+use super::{Participant, SetupOptions, ShareOptions, accept, check_config, connect};
+
+/// Size of the segments a ring forwards chunks in.
+pub const DEFAULT_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Ring where every party only talks to its predecessor and successor.
+///
+/// `share` passes each chunk around the ring in segments, forwarding every segment as
+/// soon as it arrives, so a chunk is in flight on all links at once instead of taking
+/// one full step per hop.
+pub struct Ring<C> {
+    pub(super) party_id: Id,
+    pub(super) party_count: usize,
+    pub(super) segment_size: usize,
+    /// Link to the predecessor, which only sends on it.
+    pub(super) prev: Option<C>,
+    /// Link to the successor, which only receives on it.
+    pub(super) next: Option<C>,
+}
+
+impl<C: NetIO> Ring<C> {
+    /// Establishes the links of `party_id` to its neighbours through `connector`.
+    pub async fn with_connector<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        options
+            .within_deadline(Self::establish(connector, party_id, participants, options))
+            .await
+    }
+
+    async fn establish<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        check_config(party_id, &participants)?;
+
+        let party_count = participants.len();
+        let prev_id = ((party_id as usize + party_count - 1) % party_count) as Id;
+        let next_id = ((party_id as usize + 1) % party_count) as Id;
+        let alone = party_count == 1;
+
+        let acceptor = connector
+            .bind(participants[party_id as usize].address)
+            .await?;
+
+        // Every party dials its successor, so the predecessor dials us.
+        let client_count = if alone { 0 } else { 1 };
+        let listen_task = accept(&acceptor, client_count, options, |peer_id| {
+            peer_id == prev_id
+        });
+
+        let connect_task = async {
+            if alone {
+                return anyhow::Ok(None);
+            }
+            let peer_address = participants[next_id as usize].address;
+            Ok(Some(
+                connect(connector, party_id, next_id, peer_address, options).await?,
+            ))
+        };
+
+        let (accepted, next) = tokio::try_join!(listen_task, connect_task)?;
+
+        Ok(Self {
+            party_id,
+            party_count,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            prev: accepted.into_iter().next().map(|(_, net_io)| net_io),
+            next,
+        })
+    }
+
+    /// Sets the size of the segments `share` forwards chunks in.
+    pub fn with_segment_size(mut self, segment_size: usize) -> Self {
+        assert_ne!(segment_size, 0);
+        self.segment_size = segment_size;
+        self
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        for c in self.next.into_iter().chain(self.prev) {
+            c.close().await?
+        }
+        Ok(())
+    }
+
+    fn prev_id(&self) -> Id {
+        ((self.party_id as usize + self.party_count - 1) % self.party_count) as Id
+    }
+
+    fn next_id(&self) -> Id {
+        ((self.party_id as usize + 1) % self.party_count) as Id
+    }
+}
+
+impl<C: PairWiseNetIO> Ring<C> {
+    pub async fn share(&self, data: &mut [u8], chunk_size: usize) -> anyhow::Result<()> {
+        self.share_with(data, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), bounded by the timeouts and cancel token in `options`.
+    pub async fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+
+        let (Some(prev), Some(next)) = (&self.prev, &self.next) else {
+            return Ok(());
+        };
+        // With empty chunks there is nothing to pass around the ring.
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let (prev_id, next_id) = (self.prev_id(), self.next_id());
+
+        // Chunks in the order they arrive: the predecessor's first, the successor's last.
+        let mut chunks: Vec<Option<&mut [u8]>> = data.chunks_mut(chunk_size).map(Some).collect();
+        let own = chunks[party_id].take().expect("Own chunk is present");
+        let arriving: Vec<&mut [u8]> = (1..self.party_count)
+            .map(|step| {
+                let index = (party_id + self.party_count - step) % self.party_count;
+                chunks[index].take().expect("Every chunk arrives once")
+            })
+            .collect();
+        let forwarded = arriving.len() - 1;
+
+        let (ready, mut to_forward) = mpsc::unbounded_channel::<&[u8]>();
+        let timer = &timer;
+
+        // Owns `ready`, so the sender stops once the last segment is forwarded.
+        let recv_task = async move {
+            for (step, chunk) in arriving.into_iter().enumerate() {
+                for segment in chunk.chunks_mut(self.segment_size) {
+                    let deadline = timer.round();
+                    timer
+                        .run(deadline, prev_id, Link::Ring, prev.recv(segment))
+                        .await?;
+                    // The successor already holds its own chunk, the last to arrive.
+                    if step < forwarded {
+                        let _ = ready.send(segment);
+                    }
+                }
+            }
+            anyhow::Ok(())
+        };
+
+        let send_task = async {
+            for segment in own.chunks(self.segment_size) {
+                let deadline = timer.round();
+                timer
+                    .run(deadline, next_id, Link::Ring, next.send(segment))
+                    .await?;
+            }
+            while let Some(segment) = to_forward.recv().await {
+                let deadline = timer.round();
+                timer
+                    .run(deadline, next_id, Link::Ring, next.send(segment))
+                    .await?;
+            }
+            anyhow::Ok(())
+        };
+
+        tokio::try_join!(recv_task, send_task)?;
+
+        Ok(())
+    }
+
+    pub async fn broadcast(&self, root: Id, data: &mut [u8]) -> anyhow::Result<()> {
+        self.broadcast_with(root, data, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends `data` of `root` around the ring in segments, each party forwarding a
+    /// segment as soon as it arrives, except the root's predecessor which ends the ring.
+    pub async fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let (Some(prev), Some(next)) = (&self.prev, &self.next) else {
+            return Ok(());
+        };
+
+        let timer = options.start();
+        let (prev_id, next_id) = (self.prev_id(), self.next_id());
+
+        if self.party_id == root {
+            for segment in data.chunks(self.segment_size) {
+                let deadline = timer.round();
+                timer
+                    .run(deadline, next_id, Link::Ring, next.send(segment))
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let forward = next_id != root;
+        let (ready, mut to_forward) = mpsc::unbounded_channel::<&[u8]>();
+        let timer = &timer;
+
+        // Owns `ready`, so the sender stops once the last segment is forwarded.
+        let recv_task = async move {
+            for segment in data.chunks_mut(self.segment_size) {
+                let deadline = timer.round();
+                timer
+                    .run(deadline, prev_id, Link::Ring, prev.recv(segment))
+                    .await?;
+                if forward {
+                    let _ = ready.send(segment);
+                }
+            }
+            anyhow::Ok(())
+        };
+
+        let send_task = async {
+            while let Some(segment) = to_forward.recv().await {
+                let deadline = timer.round();
+                timer
+                    .run(deadline, next_id, Link::Ring, next.send(segment))
+                    .await?;
+            }
+            anyhow::Ok(())
+        };
+
+        tokio::try_join!(recv_task, send_task)?;
+
+        Ok(())
+    }
+
+    pub async fn barrier(&self) -> anyhow::Result<()> {
+        self.barrier_with(&ShareOptions::default()).await
+    }
+
+    /// Returns once every party has entered the barrier, by sharing a one-byte token
+    /// around the ring: no party can finish before every token has been sent.
+    pub async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+        let mut tokens = vec![0; self.party_count];
+        self.share_with(&mut tokens, 1, options).await
+    }
+}
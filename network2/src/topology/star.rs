@@ -0,0 +1,253 @@
+use futures::future::try_join_all;
+
+use crate::{Connector, Error, Id, Link, NetIO, PairWiseNetIO};
+
+use super::{Participant, SetupOptions, ShareOptions, accept, check_config, connect};
+
+/// Star where every party only talks to a hub, which relays everything.
+///
+/// Only the hub listens, so the other parties merely need to be able to dial out to
+/// it, e.g. from behind a NAT.
+pub struct Star<C> {
+    pub(super) party_id: Id,
+    pub(super) party_count: usize,
+    pub(super) hub: Id,
+    /// Links to every other party, ordered by id, at the hub; the link to the hub
+    /// elsewhere.
+    pub(super) connections: Vec<C>,
+}
+
+impl<C: NetIO> Star<C> {
+    /// Establishes the links of `party_id` to `hub`, or of the hub to everyone else,
+    /// through `connector`.
+    pub async fn with_connector<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        hub: Id,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        options
+            .within_deadline(Self::establish(
+                connector,
+                party_id,
+                participants,
+                hub,
+                options,
+            ))
+            .await
+    }
+
+    async fn establish<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        hub: Id,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        check_config(party_id, &participants)?;
+
+        let party_count = participants.len();
+        if hub as usize >= party_count {
+            return Err(Error::InvalidConfig(format!(
+                "hub {hub} is not among {party_count} participants"
+            ))
+            .into());
+        }
+
+        let connections = if party_id == hub {
+            let acceptor = connector.bind(participants[hub as usize].address).await?;
+
+            let mut accepted = accept(&acceptor, party_count - 1, options, |peer_id| {
+                peer_id != hub && (peer_id as usize) < party_count
+            })
+            .await?;
+
+            accepted.sort_unstable_by_key(|a| a.0);
+
+            if let Some(pair) = accepted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(Error::DuplicateConnection { peer: pair[0].0 }.into());
+            }
+
+            accepted.into_iter().map(|(_, net_io)| net_io).collect()
+        } else {
+            let hub_address = participants[hub as usize].address;
+            vec![connect(connector, party_id, hub, hub_address, options).await?]
+        };
+
+        Ok(Self {
+            party_id,
+            party_count,
+            hub,
+            connections,
+        })
+    }
+
+    pub fn hub(&self) -> Id {
+        self.hub
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        for c in self.connections {
+            c.close().await?
+        }
+        Ok(())
+    }
+}
+
+impl<C: PairWiseNetIO> Star<C> {
+    pub async fn share(&self, data: &mut [u8], chunk_size: usize) -> anyhow::Result<()> {
+        self.share_with(data, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), bounded by the timeouts and cancel token in `options`.
+    ///
+    /// The hub gathers every chunk and then sends each party the chunks it lacks.
+    pub async fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+
+        // With empty chunks there is nothing to relay through the hub.
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+
+        if self.party_id != self.hub {
+            let conn = &self.connections[0];
+            let (before, rest) = data.split_at_mut(chunk_size * party_id);
+            let (own, after) = rest.split_at_mut(chunk_size);
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, self.hub, Link::Star, conn.send(own))
+                .await?;
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, self.hub, Link::Star, conn.recv(before))
+                .await?;
+            timer
+                .run(deadline, self.hub, Link::Star, conn.recv(after))
+                .await?;
+
+            return Ok(());
+        }
+
+        let (recv_chunks1, others) = data.split_at_mut(chunk_size * party_id);
+        let (_, recv_chunks2) = others.split_at_mut(chunk_size);
+
+        let deadline = timer.round();
+        let recv_tasks = self
+            .peers()
+            .zip(
+                recv_chunks1
+                    .chunks_exact_mut(chunk_size)
+                    .chain(recv_chunks2.chunks_exact_mut(chunk_size)),
+            )
+            .map(|((peer, conn), recv_chunk)| {
+                timer.run(deadline, peer, Link::Star, conn.recv(recv_chunk))
+            });
+        try_join_all(recv_tasks).await?;
+
+        let data = &*data;
+        let deadline = timer.round();
+        let send_tasks = self.peers().map(|(peer, conn)| {
+            let (before, rest) = data.split_at(chunk_size * peer as usize);
+            let after = &rest[chunk_size..];
+            timer.run(deadline, peer, Link::Star, async move {
+                conn.send(before).await?;
+                conn.send(after).await
+            })
+        });
+        try_join_all(send_tasks).await?;
+
+        Ok(())
+    }
+
+    pub async fn broadcast(&self, root: Id, data: &mut [u8]) -> anyhow::Result<()> {
+        self.broadcast_with(root, data, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends `data` of `root` to every party, relayed by the hub.
+    pub async fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let timer = options.start();
+
+        if self.party_id != self.hub {
+            let conn = &self.connections[0];
+            let deadline = timer.round();
+            return if root == self.party_id {
+                timer
+                    .run(deadline, self.hub, Link::Star, conn.send(data))
+                    .await
+            } else {
+                timer
+                    .run(deadline, self.hub, Link::Star, conn.recv(data))
+                    .await
+            };
+        }
+
+        if root != self.hub {
+            let deadline = timer.round();
+            timer
+                .run(deadline, root, Link::Star, self.connection(root).recv(data))
+                .await?;
+        }
+
+        let data = &*data;
+        let deadline = timer.round();
+        let send_tasks = self
+            .peers()
+            .filter(|(peer, _)| *peer != root)
+            .map(|(peer, conn)| timer.run(deadline, peer, Link::Star, conn.send(data)));
+        try_join_all(send_tasks).await?;
+
+        Ok(())
+    }
+
+    pub async fn barrier(&self) -> anyhow::Result<()> {
+        self.barrier_with(&ShareOptions::default()).await
+    }
+
+    /// Returns once every party has entered the barrier: the hub collects a token from
+    /// every party before sending any back.
+    pub async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+        let mut tokens = vec![0; self.party_count];
+        self.share_with(&mut tokens, 1, options).await
+    }
+
+    /// Links of the hub paired with the id of the party at the other end.
+    fn peers(&self) -> impl Iterator<Item = (Id, &C)> {
+        self.connections.iter().enumerate().map(|(i, conn)| {
+            let peer = if i < self.hub as usize { i } else { i + 1 };
+            (peer as Id, conn)
+        })
+    }
+
+    /// Link of the hub to `peer`, which must not be the hub.
+    fn connection(&self, peer: Id) -> &C {
+        let index = if peer < self.hub { peer } else { peer - 1 };
+        &self.connections[index as usize]
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use crate::{Id, Link};
+
+/// Transfers of one instrumented `share`, in the order they ran.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShareStats {
+    pub rounds: Vec<RoundStats>,
+}
+
+impl ShareStats {
+    /// Wall-clock time spent in transfers, excluding local work between them.
+    pub fn duration(&self) -> Duration {
+        self.rounds.iter().map(|round| round.duration).sum()
+    }
+
+    /// Transfers of hypercube dimension `dimension`, leaving out the fold links.
+    pub fn dimension(&self, dimension: u32) -> impl Iterator<Item = &RoundStats> {
+        self.rounds
+            .iter()
+            .filter(move |round| round.link == Link::Dimension(dimension))
+    }
+}
+
+/// One transfer with a single peer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundStats {
+    pub peer: Id,
+    pub link: Link,
+    pub duration: Duration,
+    pub sent: usize,
+    pub received: usize,
+}
+
+impl RoundStats {
+    /// Bytes moved per second in both directions together, or `None` if the transfer
+    /// took no measurable time.
+    pub fn bandwidth(&self) -> Option<f64> {
+        let seconds = self.duration.as_secs_f64();
+        (seconds > 0.0).then(|| (self.sent + self.received) as f64 / seconds)
+    }
+}
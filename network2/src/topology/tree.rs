@@ -0,0 +1,1337 @@
+use std::{ops::Range, time::Instant};
+
+use crate::{Connector, Error, Id, Link, NetIO, Reducer, Role, TreeNetIO};
+
+use super::{
+    LENGTH_SIZE, Participant, RoundStats, SetupOptions, ShareOptions, ShareStats, accept,
+    check_config, connect, decode_lengths, offsets,
+};
+
+/// Hypercube allgather over any number of parties.
+///
+/// The first `2^log_n` parties form a hypercube. When the party count is not a
+/// power of two, each remaining party `i` is folded onto party `i - 2^log_n`,
+/// which carries its chunk through the hypercube rounds and hands back the
+/// full result afterwards.
+pub struct Tree<C> {
+    pub(super) party_id: Id,
+    pub(super) party_count: usize,
+    pub(super) log_n: u32,
+    pub(super) connections: Vec<C>,
+    pub(super) fold: Option<C>,
+}
+
+impl<C: NetIO> Tree<C> {
+    /// Establishes the hypercube and fold links of `party_id` through `connector`.
+    pub async fn with_connector<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        options
+            .within_deadline(Self::establish(connector, party_id, participants, options))
+            .await
+    }
+
+    async fn establish<T>(
+        connector: &T,
+        party_id: Id,
+        participants: Vec<Participant>,
+        options: &SetupOptions,
+    ) -> anyhow::Result<Self>
+    where
+        T: Connector<NetIO = C>,
+    {
+        check_config(party_id, &participants)?;
+
+        let party_count = participants.len();
+        let cube_size = cube_size(party_count);
+        let in_cube = (party_id as usize) < cube_size;
+
+        let acceptor = connector
+            .bind(participants[party_id as usize].address)
+            .await?;
+
+        let log_n = cube_size.trailing_zeros();
+
+        let mut client_count = 0;
+        if in_cube {
+            client_count += log_n as usize - party_id.count_ones() as usize;
+            if party_id as usize + cube_size < party_count {
+                client_count += 1;
+            }
+        }
+
+        // Slot of a link: its hypercube dimension, or `log_n` for the fold partner.
+        let slot_of = |peer_id: Id| {
+            let (party, peer) = (party_id as usize, peer_id as usize);
+            let mask = party ^ peer;
+            if peer >= party_count {
+                None
+            } else if party + cube_size == peer || peer + cube_size == party {
+                Some(log_n as usize)
+            } else if in_cube && peer < cube_size && mask.is_power_of_two() {
+                Some(mask.trailing_zeros() as usize)
+            } else {
+                None
+            }
+        };
+
+        // println!("Party {party_id}: Waiting for Connection Count {client_count}.");
+        // Only higher parties dial us.
+        let listen_task = accept(&acceptor, client_count, options, |peer_id| {
+            peer_id > party_id && slot_of(peer_id).is_some()
+        });
+
+        let connect_task = async {
+            let mut connected = Vec::new();
+            if in_cube {
+                for i in 0..log_n {
+                    let peer_id = party_id ^ (1 << i);
+                    if peer_id < party_id {
+                        // println!("Party {party_id}: Connect to Party {peer_id}.");
+                        let peer_address = participants[peer_id as usize].address;
+                        connected.push((
+                            peer_id,
+                            connect(connector, party_id, peer_id, peer_address, options).await?,
+                        ));
+                    }
+                }
+            } else {
+                let peer_id = (party_id as usize - cube_size) as Id;
+                let peer_address = participants[peer_id as usize].address;
+                connected.push((
+                    peer_id,
+                    connect(connector, party_id, peer_id, peer_address, options).await?,
+                ));
+            }
+            anyhow::Ok(connected)
+        };
+
+        let (accepted, connected) = tokio::try_join!(listen_task, connect_task)?;
+
+        let mut connections: Vec<_> = (0..=log_n).map(|_| None).collect();
+
+        for (peer_id, net_io) in accepted.into_iter().chain(connected) {
+            let Some(index) = slot_of(peer_id) else {
+                return Err(Error::UnexpectedPeer {
+                    claimed_id: peer_id,
+                }
+                .into());
+            };
+
+            if connections[index].is_some() {
+                return Err(Error::DuplicateConnection { peer: peer_id }.into());
+            } else {
+                connections[index] = Some(net_io);
+            }
+        }
+
+        let fold = connections.pop().unwrap();
+
+        let connections = if in_cube {
+            connections
+                .into_iter()
+                .map(|a| a.expect("All connections should be established!"))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            party_id,
+            party_count,
+            log_n,
+            connections,
+            fold,
+        })
+    }
+
+    pub fn log_n(&self) -> u32 {
+        self.log_n
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        for c in self.connections.into_iter().chain(self.fold) {
+            c.close().await?
+        }
+        Ok(())
+    }
+}
+
+impl<C: TreeNetIO> Tree<C> {
+    pub async fn share(&self, data: &mut [u8], chunk_size: usize) -> anyhow::Result<()> {
+        self.share_with(data, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), bounded by the timeouts and cancel token in `options`.
+    pub async fn share_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+
+        let offsets: Vec<_> = (0..=self.party_count).map(|i| chunk_size * i).collect();
+        self.allgather(data, &offsets, options, Recorder::default())
+            .await
+    }
+
+    /// Like [`share_with`](Self::share_with), also timing every transfer of every
+    /// hypercube dimension and fold step to tell latency- from bandwidth-bound rounds.
+    pub async fn share_with_stats(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<ShareStats> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+
+        let mut stats = ShareStats::default();
+        let offsets: Vec<_> = (0..=self.party_count).map(|i| chunk_size * i).collect();
+        let recorder = Recorder {
+            stats: Some(&mut stats),
+        };
+        self.allgather(data, &offsets, options, recorder).await?;
+        Ok(stats)
+    }
+
+    pub async fn allgatherv(
+        &self,
+        data: &[u8],
+        lengths: Option<&[usize]>,
+        out: &mut Vec<u8>,
+    ) -> anyhow::Result<Vec<usize>> {
+        self.allgatherv_with(data, lengths, out, &ShareOptions::default())
+            .await
+    }
+
+    /// Like [`share`](Self::share), but every party contributes a `data` of its own length.
+    ///
+    /// Party `i`'s contribution ends up at `out[offsets[i]..offsets[i + 1]]`, where
+    /// `offsets` is the returned table. If the parties already know every length, they
+    /// pass them as `lengths` and skip the round that exchanges them.
+    ///
+    /// Fails with [`Error::LengthLimit`], before allocating `out`, if the lengths add up
+    /// to more than [`ShareOptions::max_gathered_length`].
+    pub async fn allgatherv_with(
+        &self,
+        data: &[u8],
+        lengths: Option<&[usize]>,
+        out: &mut Vec<u8>,
+        options: &ShareOptions,
+    ) -> anyhow::Result<Vec<usize>> {
+        let timer = options.start();
+        let lengths = match lengths {
+            Some(lengths) => lengths.to_vec(),
+            None => {
+                let mut buf = vec![0; LENGTH_SIZE * self.party_count];
+                let own = LENGTH_SIZE * self.party_id as usize;
+                buf[own..own + LENGTH_SIZE].copy_from_slice(&(data.len() as u64).to_le_bytes());
+                self.share_with(&mut buf, LENGTH_SIZE, &timer.remaining())
+                    .await?;
+                decode_lengths(&buf, options.max_gathered_length)?
+            }
+        };
+        assert_eq!(lengths.len(), self.party_count);
+        let offsets = offsets(
+            &lengths,
+            self.party_id,
+            data.len(),
+            options.max_gathered_length,
+        )?;
+
+        out.resize(offsets[self.party_count], 0);
+        let own = offsets[self.party_id as usize];
+        out[own..own + data.len()].copy_from_slice(data);
+
+        self.allgather(out, &offsets, &timer.remaining(), Recorder::default())
+            .await?;
+
+        Ok(offsets)
+    }
+
+    /// Hypercube allgather of the contributions at `data[offsets[i]..offsets[i + 1]]`.
+    async fn allgather(
+        &self,
+        data: &mut [u8],
+        offsets: &[usize],
+        options: &ShareOptions,
+        mut recorder: Recorder<'_>,
+    ) -> anyhow::Result<()> {
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+        let at = |i: usize| offsets[i];
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+
+            let (before, rest) = data.split_at_mut(at(party_id));
+            let (own, after) = rest.split_at_mut(at(party_id + 1) - at(party_id));
+
+            let deadline = timer.round();
+            let started = recorder.start();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(own, &mut []))
+                .await?;
+            recorder.record(started, peer, Link::Fold, own.len(), 0);
+
+            let deadline = timer.round();
+            let started = recorder.start();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(&[], before))
+                .await?;
+            recorder.record(started, peer, Link::Fold, 0, before.len());
+            let started = recorder.start();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(&[], after))
+                .await?;
+            recorder.record(started, peer, Link::Fold, 0, after.len());
+
+            return Ok(());
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+
+        if let Some(fold) = &self.fold {
+            let buf = &mut data[at(party_id + cube_size)..at(party_id + cube_size + 1)];
+            let deadline = timer.round();
+            let started = recorder.start();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], buf))
+                .await?;
+            recorder.record(started, fold_peer, Link::Fold, 0, buf.len());
+        }
+
+        for (i, net_io) in self.connections.iter().enumerate() {
+            let group = 1 << i;
+            let base = party_id & !(2 * group - 1);
+            let role = if party_id & group == 0 {
+                Role::Server
+            } else {
+                Role::Client
+            };
+            let peer = (party_id ^ group) as Id;
+            let link = Link::Dimension(i as u32);
+            let deadline = timer.round();
+
+            // The group's own chunks, then those carried on behalf of its folded parties.
+            let folded = |i: usize| at((i + cube_size).min(self.party_count));
+            let spans = [
+                (at(base), at(base + group), at(base + 2 * group)),
+                (folded(base), folded(base + group), folded(base + 2 * group)),
+            ];
+            let (sent, received) = exchanged(role, &spans);
+            let started = recorder.start();
+            let transfer = exchange(net_io, role, data, &spans);
+            timer.run(deadline, peer, link, transfer).await?;
+            recorder.record(started, peer, link, sent, received);
+        }
+
+        if let Some(fold) = &self.fold {
+            let (before, rest) = data.split_at(at(party_id + cube_size));
+            let after = &rest[at(party_id + cube_size + 1) - at(party_id + cube_size)..];
+
+            let deadline = timer.round();
+            let started = recorder.start();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(before, &mut []))
+                .await?;
+            recorder.record(started, fold_peer, Link::Fold, before.len(), 0);
+            let started = recorder.start();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(after, &mut []))
+                .await?;
+            recorder.record(started, fold_peer, Link::Fold, after.len(), 0);
+        }
+
+        Ok(())
+    }
+
+    pub async fn broadcast(&self, root: Id, data: &mut [u8]) -> anyhow::Result<()> {
+        self.broadcast_with(root, data, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends `data` of `root` to every party along a binomial tree of the hypercube.
+    ///
+    /// A folded root first hands `data` to its partner, which broadcasts in its place.
+    /// The folded parties receive it from their partners last.
+    pub async fn broadcast_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+            let deadline = timer.round();
+
+            let transfer = if root == self.party_id {
+                fold.share(data, &mut [])
+            } else {
+                fold.share(&[], data)
+            };
+            return timer.run(deadline, peer, Link::Fold, transfer).await;
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        let cube_root = root as usize % cube_size;
+
+        if root == fold_peer {
+            let fold = self.fold.as_ref().expect("Root must have a partner!");
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], data))
+                .await?;
+        }
+
+        // After round `i`, the `2^(i + 1)` parties closest to the root hold `data`.
+        let rank = party_id ^ cube_root;
+        for (i, net_io) in self.connections.iter().enumerate() {
+            let group = 1 << i;
+            let peer = (party_id ^ group) as Id;
+            let deadline = timer.round();
+
+            let transfer = if rank < group {
+                net_io.share(data, &mut [])
+            } else if rank < 2 * group {
+                net_io.share(&[], data)
+            } else {
+                continue;
+            };
+            timer
+                .run(deadline, peer, Link::Dimension(i as u32), transfer)
+                .await?;
+        }
+
+        if let Some(fold) = &self.fold
+            && root != fold_peer
+        {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn gather(
+        &self,
+        root: Id,
+        my_chunk: &[u8],
+        out: Option<&mut [u8]>,
+    ) -> anyhow::Result<()> {
+        self.gather_with(root, my_chunk, out, &ShareOptions::default())
+            .await
+    }
+
+    /// Collects every party's `my_chunk` into `out` of `root`, ordered by party id.
+    ///
+    /// Chunks flow up a binomial tree of the hypercube, so each party only buffers the
+    /// chunks of its own subtree. Only `root` passes `out`, of `my_chunk.len()` bytes
+    /// per party.
+    pub async fn gather_with(
+        &self,
+        root: Id,
+        my_chunk: &[u8],
+        out: Option<&mut [u8]>,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let chunk_size = my_chunk.len();
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+            let deadline = timer.round();
+
+            if root != self.party_id {
+                return timer
+                    .run(deadline, peer, Link::Fold, fold.share(my_chunk, &mut []))
+                    .await;
+            }
+
+            let out = out.expect("Root must provide the output buffer!");
+            assert_eq!(out.len(), chunk_size * self.party_count);
+
+            // Received in the pieces the partner sends them in.
+            let (base, rest) = out.split_at_mut(chunk_size * cube_size);
+            let (before, rest) = rest.split_at_mut(chunk_size * (party_id - cube_size));
+            let (own, after) = rest.split_at_mut(chunk_size);
+            own.copy_from_slice(my_chunk);
+
+            for buf in [base, before, after] {
+                timer
+                    .run(deadline, peer, Link::Fold, fold.share(&[], buf))
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        let rank = party_id ^ (root as usize % cube_size);
+        let height = self.height(rank);
+        let span = 1 << height;
+        let lo = party_id & !(span - 1);
+        let own = party_id - lo;
+
+        // The chunks of the subtree: its cube parties, then the parties folded onto them.
+        let mut scratch;
+        let (base, folded) = if root == self.party_id {
+            let out = out.expect("Root must provide the output buffer!");
+            assert_eq!(out.len(), chunk_size * self.party_count);
+            out.split_at_mut(chunk_size * cube_size)
+        } else {
+            scratch = vec![0; chunk_size * (span + self.folded(lo, span).len())];
+            scratch.split_at_mut(chunk_size * span)
+        };
+
+        base[chunk_size * own..chunk_size * (own + 1)].copy_from_slice(my_chunk);
+
+        if let Some(fold) = &self.fold
+            && root != fold_peer
+        {
+            let buf = &mut folded[chunk_size * own..chunk_size * (own + 1)];
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], buf))
+                .await?;
+        }
+
+        for (i, net_io) in self.connections.iter().enumerate().take(height + 1) {
+            let group = 1 << i;
+            let peer = (party_id ^ group) as Id;
+            let link = Link::Dimension(i as u32);
+            let deadline = timer.round();
+
+            if i == height {
+                // Hand the whole subtree to the parent.
+                timer
+                    .run(deadline, peer, link, net_io.share(base, &mut []))
+                    .await?;
+                if !folded.is_empty() {
+                    timer
+                        .run(deadline, peer, link, net_io.share(folded, &mut []))
+                        .await?;
+                }
+                return Ok(());
+            }
+
+            let child = (party_id ^ group) & !(group - 1);
+            let start = child - lo;
+            let buf = &mut base[chunk_size * start..chunk_size * (start + group)];
+            timer
+                .run(deadline, peer, link, net_io.share(&[], buf))
+                .await?;
+
+            let range = self.folded(child, group);
+            if !range.is_empty() {
+                let offset = lo + cube_size;
+                let buf = &mut folded
+                    [chunk_size * (range.start - offset)..chunk_size * (range.end - offset)];
+                timer
+                    .run(deadline, peer, link, net_io.share(&[], buf))
+                    .await?;
+            }
+        }
+
+        if root == fold_peer {
+            let fold = self.fold.as_ref().expect("Root must have a partner!");
+            let (before, rest) = folded.split_at(chunk_size * own);
+            let after = &rest[chunk_size..];
+
+            let deadline = timer.round();
+            for data in [&*base, before, after] {
+                timer
+                    .run(deadline, fold_peer, Link::Fold, fold.share(data, &mut []))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn scatter(
+        &self,
+        root: Id,
+        input: Option<&[u8]>,
+        my_chunk: &mut [u8],
+    ) -> anyhow::Result<()> {
+        self.scatter_with(root, input, my_chunk, &ShareOptions::default())
+            .await
+    }
+
+    /// Hands chunk `i` of `input` of `root` to party `i` as its `my_chunk`.
+    ///
+    /// The reverse of [`gather`](Self::gather): chunks flow down a binomial tree of the
+    /// hypercube and only `root` passes `input`.
+    pub async fn scatter_with(
+        &self,
+        root: Id,
+        input: Option<&[u8]>,
+        my_chunk: &mut [u8],
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let chunk_size = my_chunk.len();
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+            let deadline = timer.round();
+
+            if root != self.party_id {
+                return timer
+                    .run(deadline, peer, Link::Fold, fold.share(&[], my_chunk))
+                    .await;
+            }
+
+            let input = input.expect("Root must provide the input buffer!");
+            assert_eq!(input.len(), chunk_size * self.party_count);
+
+            let (base, rest) = input.split_at(chunk_size * cube_size);
+            let (before, rest) = rest.split_at(chunk_size * (party_id - cube_size));
+            let (own, after) = rest.split_at(chunk_size);
+            my_chunk.copy_from_slice(own);
+
+            for data in [base, before, after] {
+                timer
+                    .run(deadline, peer, Link::Fold, fold.share(data, &mut []))
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        let rank = party_id ^ (root as usize % cube_size);
+        let height = self.height(rank);
+        let span = 1 << height;
+        let lo = party_id & !(span - 1);
+        let own = party_id - lo;
+
+        // The chunks of the subtree: its cube parties, then the parties folded onto them.
+        let mut scratch = Vec::new();
+        let (base, folded) = if root == self.party_id {
+            let input = input.expect("Root must provide the input buffer!");
+            assert_eq!(input.len(), chunk_size * self.party_count);
+            input.split_at(chunk_size * cube_size)
+        } else {
+            scratch.resize(chunk_size * (span + self.folded(lo, span).len()), 0);
+            let (base, folded) = scratch.split_at_mut(chunk_size * span);
+
+            if root == fold_peer {
+                let fold = self.fold.as_ref().expect("Root must have a partner!");
+                let (before, rest) = folded.split_at_mut(chunk_size * own);
+                let after = &mut rest[chunk_size..];
+
+                let deadline = timer.round();
+                for buf in [base, before, after] {
+                    timer
+                        .run(deadline, fold_peer, Link::Fold, fold.share(&[], buf))
+                        .await?;
+                }
+            } else {
+                // Take the whole subtree from the parent.
+                let net_io = &self.connections[height];
+                let peer = (party_id ^ span) as Id;
+                let link = Link::Dimension(height as u32);
+                let deadline = timer.round();
+
+                timer
+                    .run(deadline, peer, link, net_io.share(&[], base))
+                    .await?;
+                if !folded.is_empty() {
+                    timer
+                        .run(deadline, peer, link, net_io.share(&[], folded))
+                        .await?;
+                }
+            }
+
+            scratch.split_at(chunk_size * span)
+        };
+
+        for (i, net_io) in self.connections.iter().enumerate().take(height).rev() {
+            let group = 1 << i;
+            let peer = (party_id ^ group) as Id;
+            let link = Link::Dimension(i as u32);
+            let deadline = timer.round();
+
+            let child = (party_id ^ group) & !(group - 1);
+            let start = child - lo;
+            let data = &base[chunk_size * start..chunk_size * (start + group)];
+            timer
+                .run(deadline, peer, link, net_io.share(data, &mut []))
+                .await?;
+
+            let range = self.folded(child, group);
+            if !range.is_empty() {
+                let offset = lo + cube_size;
+                let data =
+                    &folded[chunk_size * (range.start - offset)..chunk_size * (range.end - offset)];
+                timer
+                    .run(deadline, peer, link, net_io.share(data, &mut []))
+                    .await?;
+            }
+        }
+
+        my_chunk.copy_from_slice(&base[chunk_size * own..chunk_size * (own + 1)]);
+
+        if let Some(fold) = &self.fold
+            && root != fold_peer
+        {
+            let data = &folded[chunk_size * own..chunk_size * (own + 1)];
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn all_to_all(
+        &self,
+        send: &[u8],
+        recv: &mut [u8],
+        chunk_size: usize,
+    ) -> anyhow::Result<()> {
+        self.all_to_all_with(send, recv, chunk_size, &ShareOptions::default())
+            .await
+    }
+
+    /// Sends chunk `j` of `send` to party `j`, and fills chunk `i` of `recv` with the
+    /// chunk party `i` addressed to this party.
+    ///
+    /// Runs in `log_n` rounds in the manner of Bruck's algorithm: in round `i` every
+    /// party forwards over dimension `i` the blocks whose destination differs from it
+    /// in bit `i`. A folded party hands all its chunks to its partner, which routes
+    /// them along with its own.
+    pub async fn all_to_all_with(
+        &self,
+        send: &[u8],
+        recv: &mut [u8],
+        chunk_size: usize,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(send.len(), chunk_size * self.party_count);
+        assert_eq!(recv.len(), chunk_size * self.party_count);
+
+        // With empty chunks there is nothing to route.
+        if chunk_size == 0 {
+            return Ok(());
+        }
+
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(send, &mut []))
+                .await?;
+
+            let deadline = timer.round();
+            return timer
+                .run(deadline, peer, Link::Fold, fold.share(&[], recv))
+                .await;
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        // The parties a cube position stands for: itself and the party folded onto it.
+        let members = |position: usize| {
+            let folded = position + cube_size;
+            std::iter::once(position).chain((folded < self.party_count).then_some(folded))
+        };
+        let chunk = |id: usize| chunk_size * id..chunk_size * (id + 1);
+
+        let mut folded_send = Vec::new();
+        if let Some(fold) = &self.fold {
+            folded_send.resize(send.len(), 0);
+            let deadline = timer.round();
+            timer
+                .run(
+                    deadline,
+                    fold_peer,
+                    Link::Fold,
+                    fold.share(&[], &mut folded_send),
+                )
+                .await?;
+        }
+
+        // Block `j` holds the chunks between the members of two cube positions,
+        // ordered by source and then by destination. It starts out holding what this
+        // position sends to position `j`, and ends up holding what `j` sends here.
+        let sources = [send, &folded_send[..]];
+        let sources = &sources[..members(party_id).count()];
+        let mut blocks: Vec<Vec<u8>> = (0..cube_size)
+            .map(|j| {
+                sources
+                    .iter()
+                    .flat_map(|source| members(j).map(|dst| &source[chunk(dst)]))
+                    .flatten()
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        let mut packed = Vec::new();
+        let mut buf = Vec::new();
+        for (i, net_io) in self.connections.iter().enumerate() {
+            let bit = 1 << i;
+            let mask = 2 * bit - 1;
+            let peer = (party_id ^ bit) as Id;
+
+            // Blocks bound for the far side of dimension `i`. The peer sends back as many
+            // blocks, in the same order, which take over their positions.
+            let moved = || (0..cube_size).filter(move |j| (j ^ party_id) & bit != 0);
+            let received_size = |j: usize| {
+                let source = (party_id & !mask) | (j & mask);
+                let destination = (j & !mask) | (party_id & mask);
+                chunk_size * members(source).count() * members(destination).count()
+            };
+
+            packed.clear();
+            for j in moved() {
+                packed.extend_from_slice(&blocks[j]);
+            }
+            buf.resize(moved().map(received_size).sum(), 0);
+
+            let deadline = timer.round();
+            timer
+                .run(
+                    deadline,
+                    peer,
+                    Link::Dimension(i as u32),
+                    net_io.share(&packed, &mut buf),
+                )
+                .await?;
+
+            let mut rest = &buf[..];
+            for j in moved() {
+                let (block, tail) = rest.split_at(received_size(j));
+                blocks[j].clear();
+                blocks[j].extend_from_slice(block);
+                rest = tail;
+            }
+        }
+
+        let width = members(party_id).count();
+        let mut folded_recv = vec![0; folded_send.len()];
+        for (j, block) in blocks.iter().enumerate() {
+            for (source, row) in members(j).zip(block.chunks_exact(chunk_size * width)) {
+                let (own, folded) = row.split_at(chunk_size);
+                recv[chunk(source)].copy_from_slice(own);
+                if !folded.is_empty() {
+                    folded_recv[chunk(source)].copy_from_slice(folded);
+                }
+            }
+        }
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(
+                    deadline,
+                    fold_peer,
+                    Link::Fold,
+                    fold.share(&folded_recv, &mut []),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn reduce(&self, root: Id, data: &mut [u8], op: &impl Reducer) -> anyhow::Result<()> {
+        self.reduce_with(root, data, op, &ShareOptions::default())
+            .await
+    }
+
+    /// Combines the `data` of every party with `op` into `data` of `root`.
+    ///
+    /// Partial results flow up a binomial tree of the hypercube, halving the number of
+    /// active parties each round. The `data` of the other parties is left holding
+    /// partial results.
+    pub async fn reduce_with(
+        &self,
+        root: Id,
+        data: &mut [u8],
+        op: &impl Reducer,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert!((root as usize) < self.party_count);
+
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+
+            if root == self.party_id {
+                let deadline = timer.round();
+                timer
+                    .run(deadline, peer, Link::Fold, fold.share(&[], data))
+                    .await?;
+            }
+            return Ok(());
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        let mut buf = vec![0; data.len()];
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], &mut buf))
+                .await?;
+            op.reduce(data, &buf);
+        }
+
+        let rank = party_id ^ (root as usize % cube_size);
+        let height = self.height(rank);
+        for (i, net_io) in self.connections.iter().enumerate().take(height + 1) {
+            let peer = (party_id ^ (1 << i)) as Id;
+            let link = Link::Dimension(i as u32);
+            let deadline = timer.round();
+
+            if i == height {
+                return timer
+                    .run(deadline, peer, link, net_io.share(data, &mut []))
+                    .await;
+            }
+
+            timer
+                .run(deadline, peer, link, net_io.share(&[], &mut buf))
+                .await?;
+            op.reduce(data, &buf);
+        }
+
+        if root == fold_peer {
+            let fold = self.fold.as_ref().expect("Root must have a partner!");
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn allreduce(&self, data: &mut [u8], op: &impl Reducer) -> anyhow::Result<()> {
+        self.allreduce_with(data, op, &ShareOptions::default())
+            .await
+    }
+
+    /// Combines the `data` of every party with `op` into `data` of every party.
+    ///
+    /// Uses recursive doubling: in round `i` each party swaps its partial result with
+    /// its peer over dimension `i` and combines the two.
+    pub async fn allreduce_with(
+        &self,
+        data: &mut [u8],
+        op: &impl Reducer,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+
+            let deadline = timer.round();
+            return timer
+                .run(deadline, peer, Link::Fold, fold.share(&[], data))
+                .await;
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        let mut buf = vec![0; data.len()];
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], &mut buf))
+                .await?;
+            op.reduce(data, &buf);
+        }
+
+        for (i, net_io) in self.connections.iter().enumerate() {
+            let peer = (party_id ^ (1 << i)) as Id;
+            let deadline = timer.round();
+            timer
+                .run(
+                    deadline,
+                    peer,
+                    Link::Dimension(i as u32),
+                    net_io.share(data, &mut buf),
+                )
+                .await?;
+            op.reduce(data, &buf);
+        }
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn reduce_scatter(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        op: &impl Reducer,
+    ) -> anyhow::Result<()> {
+        self.reduce_scatter_with(data, chunk_size, op, &ShareOptions::default())
+            .await
+    }
+
+    /// Combines the `data` of every party with `op`, leaving each party with the result
+    /// for its own chunk, at the same place [`share`](Self::share) expects it.
+    ///
+    /// Uses recursive halving, the reverse of `share`: in round `i`, from the highest
+    /// dimension down, each party hands its peer the half of its block the peer is
+    /// responsible for and combines the half it keeps. Following it with `share` gives
+    /// every party the full result. The other chunks of `data` are left holding partial
+    /// results.
+    pub async fn reduce_scatter_with(
+        &self,
+        data: &mut [u8],
+        chunk_size: usize,
+        op: &impl Reducer,
+        options: &ShareOptions,
+    ) -> anyhow::Result<()> {
+        assert_eq!(data.len(), chunk_size * self.party_count);
+
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+        let chunks = |range: Range<usize>| chunk_size * range.start..chunk_size * range.end;
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(data, &mut []))
+                .await?;
+
+            let own = &mut data[chunks(party_id..party_id + 1)];
+            let deadline = timer.round();
+            return timer
+                .run(deadline, peer, Link::Fold, fold.share(&[], own))
+                .await;
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+        let mut buf = vec![0; data.len()];
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], &mut buf))
+                .await?;
+            op.reduce(data, &buf);
+        }
+
+        for (i, net_io) in self.connections.iter().enumerate().rev() {
+            let group = 1 << i;
+            let base = party_id & !(2 * group - 1);
+            let peer = (party_id ^ group) as Id;
+            let link = Link::Dimension(i as u32);
+            let deadline = timer.round();
+
+            // The lower half stays with the party whose bit `i` is clear.
+            let lower = party_id & group == 0;
+            let halves = |start: usize, end: usize| {
+                let mid = (start + group).min(end);
+                if lower {
+                    (start..mid, mid..end)
+                } else {
+                    (mid..end, start..mid)
+                }
+            };
+
+            let (keep, give) = halves(base, base + 2 * group);
+
+            // Chunks carried on behalf of the folded parties.
+            let start = (base + cube_size).min(self.party_count);
+            let end = (base + 2 * group + cube_size).min(self.party_count);
+            let (folded_keep, folded_give) = halves(start, end);
+
+            for (keep, give) in [(keep, give), (folded_keep, folded_give)] {
+                if keep.is_empty() && give.is_empty() {
+                    continue;
+                }
+
+                let (keep, give) = (chunks(keep), chunks(give));
+                let buf = &mut buf[..keep.len()];
+                timer
+                    .run(deadline, peer, link, net_io.share(&data[give], buf))
+                    .await?;
+                op.reduce(&mut data[keep], buf);
+            }
+        }
+
+        if let Some(fold) = &self.fold {
+            let folded = &data[chunks(fold_peer as usize..fold_peer as usize + 1)];
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(folded, &mut []))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn barrier(&self) -> anyhow::Result<()> {
+        self.barrier_with(&ShareOptions::default()).await
+    }
+
+    /// Returns once every party has entered the barrier.
+    ///
+    /// The folded parties check in with their partners, which then run a dissemination
+    /// barrier over the hypercube, swapping a token on every dimension, and release
+    /// them afterwards.
+    pub async fn barrier_with(&self, options: &ShareOptions) -> anyhow::Result<()> {
+        let timer = options.start();
+        let party_id = self.party_id as usize;
+        let cube_size = 1 << self.log_n;
+        let mut token = [0];
+
+        if party_id >= cube_size {
+            let fold = self
+                .fold
+                .as_ref()
+                .expect("Folded party must have a partner!");
+            let peer = (party_id - cube_size) as Id;
+
+            let deadline = timer.round();
+            timer
+                .run(deadline, peer, Link::Fold, fold.share(&[0], &mut []))
+                .await?;
+
+            let deadline = timer.round();
+            return timer
+                .run(deadline, peer, Link::Fold, fold.share(&[], &mut token))
+                .await;
+        }
+
+        let fold_peer = (party_id + cube_size) as Id;
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[], &mut token))
+                .await?;
+        }
+
+        for (i, net_io) in self.connections.iter().enumerate() {
+            let peer = (party_id ^ (1 << i)) as Id;
+            let deadline = timer.round();
+            timer
+                .run(
+                    deadline,
+                    peer,
+                    Link::Dimension(i as u32),
+                    net_io.share(&[0], &mut token),
+                )
+                .await?;
+        }
+
+        if let Some(fold) = &self.fold {
+            let deadline = timer.round();
+            timer
+                .run(deadline, fold_peer, Link::Fold, fold.share(&[0], &mut []))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Height of the cube party at `rank` in a binomial tree, i.e. the log of its
+    /// subtree size. Rank 0 is the root and spans the whole cube.
+    fn height(&self, rank: usize) -> usize {
+        if rank == 0 {
+            self.log_n as usize
+        } else {
+            rank.trailing_zeros() as usize
+        }
+    }
+
+    /// Ids of the parties folded onto the `span` cube parties starting at `lo`.
+    fn folded(&self, lo: usize, span: usize) -> Range<usize> {
+        let cube_size = 1 << self.log_n;
+        (lo + cube_size).min(self.party_count)..(lo + span + cube_size).min(self.party_count)
+    }
+}
+
+/// Largest power of two not exceeding `party_count`.
+pub(super) fn cube_size(party_count: usize) -> usize {
+    if party_count == 0 {
+        0
+    } else {
+        1 << party_count.ilog2()
+    }
+}
+
+/// Bytes sent and received by `exchange` over `spans`.
+fn exchanged(role: Role, spans: &[(usize, usize, usize)]) -> (usize, usize) {
+    spans
+        .iter()
+        .map(|&(start, mid, end)| match role {
+            Role::Server => (mid - start, end - mid),
+            Role::Client => (end - mid, mid - start),
+        })
+        .fold((0, 0), |(sent, received), (s, r)| (sent + s, received + r))
+}
+
+/// Collects the transfers of an allgather into [`ShareStats`], if asked to.
+#[derive(Default)]
+struct Recorder<'a> {
+    stats: Option<&'a mut ShareStats>,
+}
+
+impl Recorder<'_> {
+    /// Start of a transfer, only read from the clock when recording.
+    fn start(&self) -> Option<Instant> {
+        self.stats.as_ref().map(|_| Instant::now())
+    }
+
+    fn record(
+        &mut self,
+        started: Option<Instant>,
+        peer: Id,
+        link: Link,
+        sent: usize,
+        received: usize,
+    ) {
+        if let (Some(stats), Some(started)) = (self.stats.as_deref_mut(), started) {
+            stats.rounds.push(RoundStats {
+                peer,
+                link,
+                duration: started.elapsed(),
+                sent,
+                received,
+            });
+        }
+    }
+}
+
+/// Swaps the `start..mid` and `mid..end` halves of every `(start, mid, end)` span of
+/// `data` with the peer in a single transfer; the server owns the lower halves.
+async fn exchange<T: TreeNetIO>(
+    net_io: &T,
+    role: Role,
+    data: &mut [u8],
+    spans: &[(usize, usize, usize)],
+) -> anyhow::Result<()> {
+    let spans: Vec<_> = spans
+        .iter()
+        .copied()
+        .filter(|&(start, _, end)| start != end)
+        .collect();
+
+    // A single span is swapped in place.
+    if let [(start, mid, end)] = spans[..] {
+        let (low, high) = data[start..end].split_at_mut(mid - start);
+        return match role {
+            Role::Server => net_io.share(low, high).await,
+            Role::Client => net_io.share(high, low).await,
+        };
+    }
+
+    let (outgoing, incoming): (Vec<_>, Vec<_>) = spans
+        .iter()
+        .map(|&(start, mid, end)| match role {
+            Role::Server => (start..mid, mid..end),
+            Role::Client => (mid..end, start..mid),
+        })
+        .unzip();
+    let sent: Vec<u8> = outgoing
+        .iter()
+        .flat_map(|range| &data[range.clone()])
+        .copied()
+        .collect();
+    let mut received = vec![0; incoming.iter().map(ExactSizeIterator::len).sum()];
+    net_io.share(&sent, &mut received).await?;
+
+    let mut rest = &received[..];
+    for range in incoming {
+        let (head, tail) = rest.split_at(range.len());
+        data[range].copy_from_slice(head);
+        rest = tail;
+    }
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    time::{Instant, sleep},
+};
+
+use network2::{
+    Authenticated, Error, Id, Participant, SetupOptions, SigningKey, TcpConnector, TcpPairWise,
+    TcpTree, Tree,
+};
+
+fn signing_keys(party_count: usize) -> Vec<SigningKey> {
+    (0..party_count)
+        .map(|party_id| SigningKey::from_bytes(&[party_id as u8 + 1; 32]))
+        .collect()
+}
+
+/// Parties on consecutive localhost ports, each with the public key of `signing_keys`.
+fn keyed_participants(signing_keys: &[SigningKey], base_port: u16) -> Vec<Participant> {
+    let mut parties = Participant::from_default(signing_keys.len(), base_port).unwrap();
+    for (party, signing_key) in parties.iter_mut().zip(signing_keys) {
+        party.public_key = Some(signing_key.verifying_key());
+    }
+    parties
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn authenticated_tree_shares() {
+    let party_count = 5;
+    let signing_keys = signing_keys(party_count);
+    let parties = keyed_participants(&signing_keys, 21500);
+    let handles: Vec<_> = (0..party_count)
+        .map(|party_id| {
+            let parties = parties.clone();
+            let signing_key = signing_keys[party_id].clone();
+            tokio::spawn(async move {
+                let options = SetupOptions::default();
+                let tree = TcpTree::authenticated(party_id as Id, parties, signing_key, &options)
+                    .await
+                    .unwrap();
+                let mut data = vec![0; 4 * party_count];
+                data[4 * party_id..4 * (party_id + 1)].fill(party_id as u8);
+                tree.share(&mut data, 4).await.unwrap();
+                tree.close().await.unwrap();
+                data
+            })
+        })
+        .collect();
+    let expected: Vec<u8> = (0..party_count)
+        .flat_map(|party_id| vec![party_id as u8; 4])
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), expected);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn party_signing_with_wrong_key_is_rejected() {
+    let signing_keys = signing_keys(3);
+    let parties = keyed_participants(&signing_keys[..2], 21600);
+    let options = SetupOptions {
+        max_retries: 3,
+        accept_timeout: Some(Duration::from_secs(2)),
+        ..Default::default()
+    };
+
+    let server = tokio::spawn({
+        let (parties, signing_key) = (parties.clone(), signing_keys[0].clone());
+        async move { TcpPairWise::authenticated(0, parties, signing_key, &options).await }
+    });
+    // Party 1 signs with a key that is not the one listed for it.
+    let impostor = tokio::spawn({
+        let signing_key = signing_keys[2].clone();
+        async move { TcpPairWise::authenticated(1, parties, signing_key, &options).await }
+    });
+
+    let server_error = server.await.unwrap().err().unwrap();
+    let impostor_error = impostor.await.unwrap().err().unwrap();
+    assert!(matches!(
+        server_error.downcast_ref::<Error>(),
+        Some(Error::AcceptTimeout { .. })
+    ));
+    assert!(matches!(
+        impostor_error.downcast_ref::<Error>(),
+        Some(Error::HandshakeFailed { peer: 0 })
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn strays_claiming_a_party_id_do_not_take_its_place() {
+    let signing_keys = signing_keys(2);
+    let parties = keyed_participants(&signing_keys, 25100);
+    let options = SetupOptions {
+        accept_timeout: Some(Duration::from_secs(5)),
+        ..Default::default()
+    };
+
+    let server = tokio::spawn({
+        let (parties, signing_key) = (parties.clone(), signing_keys[0].clone());
+        async move { TcpTree::authenticated(0, parties, signing_key, &options).await }
+    });
+    let address = parties[0].address;
+    let mut closing = loop {
+        match TcpStream::connect(address).await {
+            Ok(tcp_stream) => break tcp_stream,
+            Err(_) => sleep(Duration::from_millis(10)).await,
+        }
+    };
+    let mut silent = TcpStream::connect(address).await.unwrap();
+    // Both claim to be party 1, but cannot sign for it.
+    closing.write_u32(1).await.unwrap();
+    silent.write_u32(1).await.unwrap();
+    drop(closing);
+
+    let client = TcpTree::authenticated(1, parties, signing_keys[1].clone(), &options)
+        .await
+        .unwrap();
+    let server = server.await.unwrap().unwrap();
+    let (mut server_data, mut client_data) = ([1, 0], [0, 2]);
+    tokio::try_join!(
+        server.share(&mut server_data, 1),
+        client.share(&mut client_data, 1)
+    )
+    .unwrap();
+    assert_eq!((server_data, client_data), ([1, 2], [1, 2]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_gives_up_on_a_stalled_handshake() {
+    let signing_keys = signing_keys(2);
+    let parties = keyed_participants(&signing_keys, 25600);
+
+    // Party 0 accepts the link but never answers the challenge.
+    let listener = TcpListener::bind(parties[0].address).await.unwrap();
+    let stalled = tokio::spawn(async move {
+        let (tcp_stream, _) = listener.accept().await.unwrap();
+        sleep(Duration::from_secs(5)).await;
+        drop(tcp_stream);
+    });
+
+    let connector = Authenticated::new(TcpConnector, 1, signing_keys[1].clone(), &parties)
+        .with_handshake_timeout(Duration::from_millis(200));
+    let started = Instant::now();
+    let error = Tree::with_connector(&connector, 1, parties, &SetupOptions::default())
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::HandshakeFailed { peer: 0 })
+    ));
+    // A single attempt, not a retry per timeout.
+    assert!(started.elapsed() < Duration::from_secs(2));
+    stalled.abort();
+}
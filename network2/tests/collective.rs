@@ -0,0 +1,158 @@
+use futures::future::join_all;
+use network2::{
+    Collective, Id, MemHierarchical, MemPairWise, MemRing, MemStar, MemTree, Participant,
+    TcpTopology, TopologyKind,
+};
+
+/// Protocol code written once against [`Collective`]: a share, a broadcast from every
+/// root and a barrier.
+async fn run_protocol(collective: impl Collective, chunk_size: usize) {
+    let party_count = collective.party_count();
+    let party_id = collective.party_id() as usize;
+
+    let mut data = vec![0; chunk_size * party_count];
+    data[chunk_size * party_id..chunk_size * (party_id + 1)].fill(party_id as u8 + 1);
+    collective.share(&mut data, chunk_size).await.unwrap();
+    let expected: Vec<u8> = (0..party_count)
+        .flat_map(|party_id| vec![party_id as u8 + 1; chunk_size])
+        .collect();
+    assert_eq!(data, expected);
+
+    let payload = |root: usize| -> Vec<u8> {
+        (0..chunk_size * 3)
+            .map(|index| (index * 7 + root) as u8)
+            .collect()
+    };
+    for root in 0..party_count {
+        let mut data = if party_id == root {
+            payload(root)
+        } else {
+            vec![0; chunk_size * 3]
+        };
+        collective.broadcast(root as Id, &mut data).await.unwrap();
+        assert_eq!(data, payload(root), "root {root}");
+    }
+
+    collective.barrier().await.unwrap();
+    collective.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn every_local_topology_runs_generic_protocol() {
+    let chunk_size = 5;
+    for party_count in 1..=6 {
+        join_all(
+            MemTree::local(party_count)
+                .into_iter()
+                .map(|tree| run_protocol(tree, chunk_size)),
+        )
+        .await;
+        join_all(
+            MemPairWise::local(party_count)
+                .into_iter()
+                .map(|mesh| run_protocol(mesh, chunk_size)),
+        )
+        .await;
+        join_all(
+            MemRing::local(party_count)
+                .into_iter()
+                .map(|ring| run_protocol(ring.with_segment_size(4), chunk_size)),
+        )
+        .await;
+        let hub = party_count as Id - 1;
+        join_all(
+            MemStar::local(party_count, hub)
+                .into_iter()
+                .map(|star| run_protocol(star, chunk_size)),
+        )
+        .await;
+        let regions: Vec<_> = (0..party_count)
+            .map(|party_id| Some(["a", "b"][party_id % 2]))
+            .collect();
+        join_all(
+            MemHierarchical::local(&regions)
+                .into_iter()
+                .map(|hierarchical| run_protocol(hierarchical, chunk_size)),
+        )
+        .await;
+    }
+}
+
+#[test]
+fn topology_kind_round_trips_through_its_name() {
+    for (kind, name) in [
+        (TopologyKind::Tree, "tree"),
+        (TopologyKind::PairWise, "pairwise"),
+        (TopologyKind::Ring, "ring"),
+        (TopologyKind::Star { hub: 0 }, "star"),
+        (TopologyKind::Star { hub: 2 }, "star:2"),
+        (TopologyKind::Hierarchical, "hierarchical"),
+    ] {
+        assert_eq!(name.parse::<TopologyKind>().unwrap(), kind);
+        assert_eq!(kind.to_string(), name);
+    }
+    assert!("mesh".parse::<TopologyKind>().is_err());
+    assert!("star:x".parse::<TopologyKind>().is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn topology_chosen_at_runtime_runs_generic_protocol() {
+    let party_count = 5;
+    for (index, name) in ["tree", "pairwise", "ring", "star:2", "hierarchical"]
+        .into_iter()
+        .enumerate()
+    {
+        let kind: TopologyKind = name.parse().unwrap();
+        let parties = Participant::from_default(party_count, 24000 + 100 * index as u16).unwrap();
+        let handles: Vec<_> = (0..party_count)
+            .map(|party_id| {
+                let parties = parties.clone();
+                tokio::spawn(async move {
+                    let topology = TcpTopology::new(kind, party_id as Id, parties)
+                        .await
+                        .unwrap();
+                    assert_eq!(topology.kind(), kind);
+                    run_protocol(topology, 70_000).await;
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn tree_and_pair_wise_move_empty_chunks() {
+    for party_count in 1..=6 {
+        let trees = MemTree::local(party_count);
+        let meshes = MemPairWise::local(party_count);
+        join_all(trees.into_iter().zip(meshes).enumerate().map(
+            |(party_id, (tree, mesh))| async move {
+                tree.share(&mut [], 0).await.unwrap();
+                mesh.share(&mut [], 0).await.unwrap();
+                tree.all_to_all(&[], &mut [], 0).await.unwrap();
+                mesh.all_to_all(&[], &mut [], 0).await.unwrap();
+                for root in 0..party_count {
+                    let is_root = root == party_id;
+                    let root = root as Id;
+                    tree.gather(root, &[], is_root.then_some(&mut []))
+                        .await
+                        .unwrap();
+                    mesh.gather(root, &[], is_root.then_some(&mut []))
+                        .await
+                        .unwrap();
+                    tree.scatter(root, is_root.then_some(&[]), &mut [])
+                        .await
+                        .unwrap();
+                    mesh.scatter(root, is_root.then_some(&[]), &mut [])
+                        .await
+                        .unwrap();
+                }
+                tree.close().await.unwrap();
+                mesh.close().await.unwrap();
+            },
+        ))
+        .await;
+    }
+}
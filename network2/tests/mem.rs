@@ -0,0 +1,601 @@
+use std::time::Duration;
+
+use futures::future::join_all;
+use network2::{
+    AddMod, AddU32, AddU64, CancellationToken, DEFAULT_MAX_GATHERED_LENGTH, Error, Link,
+    MemHierarchical, MemPairWise, MemRing, MemStar, MemTree, Reducer, RoundStats, ShareOptions,
+    Xor,
+};
+
+/// Buffer of `party_id` before a share: only its own chunk is filled in.
+fn contribution(party_count: usize, party_id: usize, chunk_size: usize) -> Vec<u8> {
+    let mut data = vec![0; chunk_size * party_count];
+    let own = &mut data[chunk_size * party_id..chunk_size * (party_id + 1)];
+    for (offset, byte) in own.iter_mut().enumerate() {
+        *byte = (party_id * 31 + offset * 7) as u8;
+    }
+    data
+}
+
+/// Buffer every party holds after a share.
+fn shared(party_count: usize, chunk_size: usize) -> Vec<u8> {
+    (0..party_count)
+        .flat_map(|party_id| {
+            let data = contribution(party_count, party_id, chunk_size);
+            data[chunk_size * party_id..chunk_size * (party_id + 1)].to_vec()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn tree_and_pair_wise_share_locally() {
+    let chunk_size = 3000;
+    for party_count in 1..=17 {
+        let trees = MemTree::local(party_count);
+        let meshes = MemPairWise::local(party_count);
+        let expected = &shared(party_count, chunk_size);
+        join_all(trees.into_iter().zip(meshes).enumerate().map(
+            |(party_id, (tree, mesh))| async move {
+                let mut tree_data = contribution(party_count, party_id, chunk_size);
+                tree.share(&mut tree_data, chunk_size).await.unwrap();
+                tree.close().await.unwrap();
+
+                let mut mesh_data = contribution(party_count, party_id, chunk_size);
+                mesh.share(&mut mesh_data, chunk_size).await.unwrap();
+                mesh.close().await.unwrap();
+
+                assert_eq!(&tree_data, expected, "tree, {party_count} parties");
+                assert_eq!(&mesh_data, expected, "mesh, {party_count} parties");
+            },
+        ))
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn share_names_stalled_link_and_stops_on_cancel() {
+    // Parties 1 and 2 never share, so party 0 first waits on its fold partner 2.
+    let mut trees = MemTree::local(3);
+    let _silent = trees.split_off(1);
+    let tree = trees.pop().unwrap();
+    let mut data = vec![0; 30];
+
+    let options = ShareOptions {
+        round_timeout: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let error = tree.share_with(&mut data, 10, &options).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ShareTimeout {
+            peer: 2,
+            link: Link::Fold
+        })
+    ));
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let options = ShareOptions {
+        cancel: Some(cancel),
+        ..Default::default()
+    };
+    let error = tree.share_with(&mut data, 10, &options).await.unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::Cancelled)
+    ));
+
+    let meshes = MemPairWise::local(3);
+    let options = ShareOptions {
+        timeout: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let error = meshes[1]
+        .share_with(&mut data, 10, &options)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ShareTimeout {
+            link: Link::Mesh,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn broadcast_reaches_every_party_from_every_root() {
+    for party_count in 1..=13 {
+        for root in 0..party_count {
+            for length in [0, 5, 100_000] {
+                let trees = MemTree::local(party_count);
+                let meshes = MemPairWise::local(party_count);
+                let payload: Vec<u8> = (0..length).map(|index| (index * 7 + root) as u8).collect();
+                let payload = &payload;
+                join_all(trees.iter().zip(&meshes).enumerate().map(
+                    |(party_id, (tree, mesh))| async move {
+                        let initial = |party_id| {
+                            if party_id == root {
+                                payload.clone()
+                            } else {
+                                vec![0; length]
+                            }
+                        };
+
+                        let mut tree_data = initial(party_id);
+                        tree.broadcast(root as u32, &mut tree_data).await.unwrap();
+                        let mut mesh_data = initial(party_id);
+                        mesh.broadcast(root as u32, &mut mesh_data).await.unwrap();
+
+                        assert_eq!(
+                            &tree_data, payload,
+                            "tree, {party_count} parties, root {root}"
+                        );
+                        assert_eq!(
+                            &mesh_data, payload,
+                            "mesh, {party_count} parties, root {root}"
+                        );
+                    },
+                ))
+                .await;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn gather_and_scatter_round_trip_through_every_root() {
+    for party_count in 1..=13 {
+        for root in 0..party_count {
+            for chunk_size in [1, 3, 40_000] {
+                let trees = MemTree::local(party_count);
+                let meshes = MemPairWise::local(party_count);
+                let full: Vec<u8> = (0..chunk_size * party_count)
+                    .map(|index| (index * 7 + 3) as u8)
+                    .collect();
+                let full = &full;
+                join_all(trees.iter().zip(&meshes).enumerate().map(
+                    |(party_id, (tree, mesh))| async move {
+                        let own = &full[chunk_size * party_id..chunk_size * (party_id + 1)];
+                        let is_root = party_id == root;
+                        let root = root as u32;
+
+                        let mut tree_gathered = vec![0; chunk_size * party_count];
+                        let out = is_root.then_some(&mut tree_gathered[..]);
+                        tree.gather(root, own, out).await.unwrap();
+                        let mut mesh_gathered = vec![0; chunk_size * party_count];
+                        let out = is_root.then_some(&mut mesh_gathered[..]);
+                        mesh.gather(root, own, out).await.unwrap();
+
+                        let input = is_root.then_some(&full[..]);
+                        let mut tree_chunk = vec![0; chunk_size];
+                        tree.scatter(root, input, &mut tree_chunk).await.unwrap();
+                        let mut mesh_chunk = vec![0; chunk_size];
+                        mesh.scatter(root, input, &mut mesh_chunk).await.unwrap();
+
+                        if is_root {
+                            assert_eq!(&tree_gathered, full, "tree gather, root {root}");
+                            assert_eq!(&mesh_gathered, full, "mesh gather, root {root}");
+                        }
+                        assert_eq!(tree_chunk, own, "tree scatter, party {party_id}");
+                        assert_eq!(mesh_chunk, own, "mesh scatter, party {party_id}");
+                    },
+                ))
+                .await;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn all_to_all_delivers_each_slot_to_its_party() {
+    let byte = |from: usize, to: usize, offset: usize| (from * 31 + to * 7 + offset) as u8;
+    for party_count in 1..=13 {
+        for chunk_size in [1, 3, 20_000] {
+            let trees = MemTree::local(party_count);
+            let meshes = MemPairWise::local(party_count);
+            join_all(trees.iter().zip(&meshes).enumerate().map(
+                |(party_id, (tree, mesh))| async move {
+                    let send: Vec<u8> = (0..party_count)
+                        .flat_map(|to| {
+                            (0..chunk_size).map(move |offset| byte(party_id, to, offset))
+                        })
+                        .collect();
+                    let expected: Vec<u8> = (0..party_count)
+                        .flat_map(|from| {
+                            (0..chunk_size).map(move |offset| byte(from, party_id, offset))
+                        })
+                        .collect();
+
+                    let mut tree_recv = vec![0; chunk_size * party_count];
+                    tree.all_to_all(&send, &mut tree_recv, chunk_size)
+                        .await
+                        .unwrap();
+                    let mut mesh_recv = vec![0; chunk_size * party_count];
+                    mesh.all_to_all(&send, &mut mesh_recv, chunk_size)
+                        .await
+                        .unwrap();
+
+                    assert_eq!(
+                        tree_recv, expected,
+                        "tree, party {party_id} of {party_count}"
+                    );
+                    assert_eq!(
+                        mesh_recv, expected,
+                        "mesh, party {party_id} of {party_count}"
+                    );
+                },
+            ))
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn reduce_and_allreduce_combine_every_contribution() {
+    let modulus = 1_000_003;
+    let values = |party_id: usize| -> Vec<u8> {
+        (0..1000u64)
+            .flat_map(|index| {
+                ((party_id as u64 * 7919 + index * 31) * 12345 % modulus).to_le_bytes()
+            })
+            .collect()
+    };
+    for party_count in 1..=13 {
+        let mut xored = values(0);
+        let mut summed = values(0);
+        let mut summed_mod = values(0);
+        for party_id in 1..party_count {
+            Xor.reduce(&mut xored, &values(party_id));
+            AddU32.reduce(&mut summed, &values(party_id));
+            AddMod::new(modulus).reduce(&mut summed_mod, &values(party_id));
+        }
+        let (xored, summed, summed_mod) = (&xored, &summed, &summed_mod);
+
+        for root in 0..party_count {
+            let trees = MemTree::local(party_count);
+            join_all(trees.iter().enumerate().map(|(party_id, tree)| async move {
+                let mut data = values(party_id);
+                tree.reduce(root as u32, &mut data, &Xor).await.unwrap();
+                if party_id == root {
+                    assert_eq!(&data, xored, "reduce, {party_count} parties, root {root}");
+                }
+
+                let mut data = values(party_id);
+                tree.allreduce(&mut data, &AddU32).await.unwrap();
+                assert_eq!(&data, summed, "allreduce, {party_count} parties");
+
+                let mut data = values(party_id);
+                tree.allreduce(&mut data, &AddMod::new(modulus))
+                    .await
+                    .unwrap();
+                assert_eq!(&data, summed_mod, "allreduce mod, {party_count} parties");
+            }))
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn reduce_scatter_then_share_equals_allreduce() {
+    for party_count in 1..=13 {
+        for chunk_size in [8, 8 * 3000] {
+            let values = |party_id: usize| -> Vec<u8> {
+                (0..(chunk_size * party_count / 8) as u64)
+                    .flat_map(|index| {
+                        (party_id as u64 * 7919 + index * 31)
+                            .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                            .to_le_bytes()
+                    })
+                    .collect()
+            };
+            let mut summed = values(0);
+            for party_id in 1..party_count {
+                AddU64.reduce(&mut summed, &values(party_id));
+            }
+            let summed = &summed;
+
+            let trees = MemTree::local(party_count);
+            join_all(trees.iter().enumerate().map(|(party_id, tree)| async move {
+                let own = chunk_size * party_id..chunk_size * (party_id + 1);
+                let mut data = values(party_id);
+                tree.reduce_scatter(&mut data, chunk_size, &AddU64)
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    data[own.clone()],
+                    summed[own],
+                    "party {party_id} of {party_count}"
+                );
+
+                tree.share(&mut data, chunk_size).await.unwrap();
+                assert_eq!(&data, summed, "{party_count} parties");
+            }))
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn allgatherv_places_contributions_of_any_length() {
+    for party_count in 1..=13 {
+        for scale in [1, 5000] {
+            let length = |party_id: usize| (party_id * 37 % 5) * scale;
+            let proof = |party_id: usize| -> Vec<u8> {
+                (0..length(party_id))
+                    .map(|index| (party_id * 13 + index) as u8)
+                    .collect()
+            };
+            let expected: Vec<u8> = (0..party_count).flat_map(proof).collect();
+            let lengths: Vec<usize> = (0..party_count).map(length).collect();
+            let (expected, lengths) = (&expected, &lengths);
+
+            let trees = MemTree::local(party_count);
+            let meshes = MemPairWise::local(party_count);
+            join_all(trees.iter().zip(&meshes).enumerate().map(
+                |(party_id, (tree, mesh))| async move {
+                    let mut out = Vec::new();
+                    let offsets = tree
+                        .allgatherv(&proof(party_id), None, &mut out)
+                        .await
+                        .unwrap();
+                    assert_eq!(&out, expected, "tree, {party_count} parties");
+                    assert_eq!(offsets.len(), party_count + 1);
+
+                    let mut out = Vec::new();
+                    tree.allgatherv(&proof(party_id), Some(lengths), &mut out)
+                        .await
+                        .unwrap();
+                    assert_eq!(&out, expected, "tree with known lengths");
+
+                    // Whatever the buffer held before is replaced.
+                    let mut out = vec![9; 3];
+                    let mesh_offsets = mesh
+                        .allgatherv(&proof(party_id), None, &mut out)
+                        .await
+                        .unwrap();
+                    assert_eq!(&out, expected, "mesh, {party_count} parties");
+                    assert_eq!(offsets, mesh_offsets);
+                },
+            ))
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn allgatherv_rejects_lengths_over_the_limit() {
+    let trees = MemTree::local(3);
+    let meshes = MemPairWise::local(3);
+    // The first sum overflows, the second only exceeds the limit.
+    for lengths in [
+        [0, usize::MAX, usize::MAX],
+        [0, DEFAULT_MAX_GATHERED_LENGTH, 1],
+    ] {
+        let lengths = &lengths[..];
+        let mut out = Vec::new();
+        let tree_error = trees[0]
+            .allgatherv(&[], Some(lengths), &mut out)
+            .await
+            .unwrap_err();
+        let mesh_error = meshes[0]
+            .allgatherv(&[], Some(lengths), &mut out)
+            .await
+            .unwrap_err();
+        for error in [tree_error, mesh_error] {
+            assert!(matches!(
+                error.downcast_ref::<Error>(),
+                Some(Error::LengthLimit { .. })
+            ));
+        }
+    }
+}
+
+#[tokio::test]
+async fn allgatherv_rejects_announced_lengths_before_allocating() {
+    let limited = ShareOptions {
+        max_gathered_length: 8,
+        ..Default::default()
+    };
+    let trees = MemTree::local(2);
+    let meshes = MemPairWise::local(2);
+    let mut errors = Vec::new();
+
+    // Party 1 announces 16 bytes, more than party 0 is willing to gather. As party 0
+    // contributes nothing, party 1 does not wait on it.
+    let mut out = Vec::new();
+    let mut peer_out = Vec::new();
+    let (result, _) = tokio::join!(
+        trees[0].allgatherv_with(&[], None, &mut out, &limited),
+        trees[1].allgatherv(&[1; 16], None, &mut peer_out),
+    );
+    errors.push(result.unwrap_err());
+    assert_eq!(out.capacity(), 0);
+
+    let (result, _) = tokio::join!(
+        meshes[0].allgatherv_with(&[], None, &mut out, &limited),
+        meshes[1].allgatherv(&[1; 16], None, &mut peer_out),
+    );
+    errors.push(result.unwrap_err());
+    assert_eq!(out.capacity(), 0);
+
+    for error in errors {
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::LengthLimit { max: 8 })
+        ));
+    }
+}
+
+#[tokio::test]
+async fn barrier_completes_repeatedly() {
+    for party_count in 1..=13 {
+        let trees = MemTree::local(party_count);
+        let meshes = MemPairWise::local(party_count);
+        join_all(trees.iter().zip(&meshes).map(|(tree, mesh)| async move {
+            for _ in 0..3 {
+                tree.barrier().await.unwrap();
+                mesh.barrier().await.unwrap();
+            }
+        }))
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn ring_shares_with_any_segment_size() {
+    for party_count in 1..=9 {
+        for (chunk_size, segment_size) in
+            [(0, 1), (1, 1), (10, 3), (100_000, 65536), (5000, 100_000)]
+        {
+            let rings = MemRing::local(party_count)
+                .into_iter()
+                .map(|ring| ring.with_segment_size(segment_size));
+            let expected = &shared(party_count, chunk_size);
+            join_all(rings.enumerate().map(|(party_id, ring)| async move {
+                let mut data = contribution(party_count, party_id, chunk_size);
+                ring.share(&mut data, chunk_size).await.unwrap();
+                assert_eq!(&data, expected, "{party_count} parties, chunk {chunk_size}");
+            }))
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn star_shares_and_broadcasts_through_every_hub() {
+    let chunk_size = 30_000;
+    for party_count in 1..=7 {
+        let expected = &shared(party_count, chunk_size);
+        for hub in 0..party_count as u32 {
+            let stars = MemStar::local(party_count, hub);
+            join_all(stars.iter().enumerate().map(|(party_id, star)| async move {
+                let mut data = contribution(party_count, party_id, chunk_size);
+                star.share(&mut data, chunk_size).await.unwrap();
+                assert_eq!(&data, expected, "{party_count} parties, hub {hub}");
+                star.share(&mut [], 0).await.unwrap();
+                star.barrier().await.unwrap();
+
+                for root in 0..party_count {
+                    let mut data = if root == party_id {
+                        expected.clone()
+                    } else {
+                        vec![0; expected.len()]
+                    };
+                    star.broadcast(root as u32, &mut data).await.unwrap();
+                    assert_eq!(&data, expected, "hub {hub}, root {root}");
+                }
+            }))
+            .await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn hierarchical_shares_and_broadcasts_for_any_region_layout() {
+    let chunk_size = 20_000;
+    let layouts: [&[Option<&str>]; 5] = [
+        &[None],
+        &[Some("a"), Some("b")],
+        &[
+            Some("a"),
+            Some("b"),
+            Some("a"),
+            Some("c"),
+            Some("b"),
+            Some("a"),
+            None,
+        ],
+        &[Some("x"); 5],
+        &[Some("a"), Some("b"), Some("c"), Some("d"), Some("e")],
+    ];
+    for regions in layouts {
+        let party_count = regions.len();
+        let expected = &shared(party_count, chunk_size);
+        let parties = MemHierarchical::local(regions);
+        join_all(
+            parties
+                .iter()
+                .enumerate()
+                .map(|(party_id, hierarchical)| async move {
+                    let mut data = contribution(party_count, party_id, chunk_size);
+                    hierarchical.share(&mut data, chunk_size).await.unwrap();
+                    assert_eq!(&data, expected, "regions {regions:?}");
+                    hierarchical.share(&mut [], 0).await.unwrap();
+                    hierarchical.barrier().await.unwrap();
+
+                    for root in 0..party_count {
+                        let mut data = if root == party_id {
+                            expected.clone()
+                        } else {
+                            vec![0; expected.len()]
+                        };
+                        hierarchical
+                            .broadcast(root as u32, &mut data)
+                            .await
+                            .unwrap();
+                        assert_eq!(&data, expected, "regions {regions:?}, root {root}");
+                    }
+                }),
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn tree_share_stats_account_for_every_received_byte() {
+    let chunk_size = 11;
+    for party_count in 1usize..=9 {
+        let cube_size = 1 << party_count.ilog2();
+        let expected = &shared(party_count, chunk_size);
+        let trees = MemTree::local(party_count);
+        join_all(
+            trees
+                .into_iter()
+                .enumerate()
+                .map(|(party_id, tree)| async move {
+                    let mut data = contribution(party_count, party_id, chunk_size);
+                    let stats = tree
+                        .share_with_stats(&mut data, chunk_size, &ShareOptions::default())
+                        .await
+                        .unwrap();
+                    tree.close().await.unwrap();
+                    assert_eq!(&data, expected);
+
+                    let received: usize = stats.rounds.iter().map(|round| round.received).sum();
+                    for round in &stats.rounds {
+                        assert!(round.bandwidth().is_none_or(f64::is_finite), "{round:?}");
+                    }
+                    assert_eq!(received, chunk_size * (party_count - 1), "{stats:?}");
+                    if party_id < cube_size {
+                        // Folded chunks ride along, so every dimension is a single exchange.
+                        for dimension in 0..cube_size.ilog2() {
+                            assert_eq!(stats.dimension(dimension).count(), 1, "{stats:?}");
+                        }
+                    } else {
+                        // A folded party only talks to its partner: its chunk out, the rest back.
+                        assert_eq!(stats.rounds.len(), 3);
+                        assert!(stats.rounds.iter().all(|round| round.link == Link::Fold));
+                    }
+                }),
+        )
+        .await;
+    }
+}
+
+#[test]
+fn bandwidth_of_an_instant_transfer_is_unknown() {
+    let round = RoundStats {
+        peer: 1,
+        link: Link::Fold,
+        duration: Duration::ZERO,
+        sent: 10,
+        received: 10,
+    };
+    assert_eq!(round.bandwidth(), None);
+    let round = RoundStats {
+        duration: Duration::from_millis(500),
+        ..round
+    };
+    assert_eq!(round.bandwidth(), Some(40.0));
+}
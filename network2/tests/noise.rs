@@ -0,0 +1,145 @@
+#![cfg(feature = "noise")]
+
+use std::time::Duration;
+
+use network2::{
+    Error, Hierarchical, Id, NoiseConnector, NoisePairWise, NoiseTree, Participant, SetupOptions,
+    SigningKey, TcpConnector,
+};
+
+/// Parties on consecutive localhost ports, each with the public key of `signing_keys`.
+fn keyed_participants(signing_keys: &[SigningKey], base_port: u16) -> Vec<Participant> {
+    let mut parties = Participant::from_default(signing_keys.len(), base_port).unwrap();
+    for (party, signing_key) in parties.iter_mut().zip(signing_keys) {
+        party.public_key = Some(signing_key.verifying_key());
+    }
+    parties
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn encrypted_tree_and_pair_wise_share() {
+    let mut base_port = 22000;
+    for party_count in [1, 2, 3, 5, 6] {
+        // Small chunks fit one Noise frame, large ones span several.
+        for chunk_size in [10, 200_000] {
+            let signing_keys: Vec<SigningKey> = (0..party_count)
+                .map(|party_id| SigningKey::from_bytes(&[party_id as u8 + 7; 32]))
+                .collect();
+            let tree_parties = keyed_participants(&signing_keys, base_port);
+            let mesh_parties = keyed_participants(&signing_keys, base_port + 20);
+            base_port += 40;
+
+            let handles: Vec<_> = (0..party_count)
+                .map(|party_id| {
+                    let (tree_parties, mesh_parties) = (tree_parties.clone(), mesh_parties.clone());
+                    let signing_key = signing_keys[party_id].clone();
+                    tokio::spawn(async move {
+                        let own = chunk_size * party_id..chunk_size * (party_id + 1);
+
+                        let tree = NoiseTree::new(party_id as Id, tree_parties, &signing_key)
+                            .await
+                            .unwrap();
+                        let mut tree_data = vec![0; chunk_size * party_count];
+                        tree_data[own.clone()].fill(party_id as u8 + 1);
+                        // Twice, so the second share runs on advanced nonces.
+                        tree.share(&mut tree_data, chunk_size).await.unwrap();
+                        tree.share(&mut tree_data, chunk_size).await.unwrap();
+                        tree.close().await.unwrap();
+
+                        let mesh = NoisePairWise::new(party_id as Id, mesh_parties, &signing_key)
+                            .await
+                            .unwrap();
+                        let mut mesh_data = vec![0; chunk_size * party_count];
+                        mesh_data[own].fill(party_id as u8 + 1);
+                        mesh.share(&mut mesh_data, chunk_size).await.unwrap();
+                        mesh.close().await.unwrap();
+
+                        (tree_data, mesh_data)
+                    })
+                })
+                .collect();
+            let expected: Vec<u8> = (0..party_count)
+                .flat_map(|party_id| vec![party_id as u8 + 1; chunk_size])
+                .collect();
+            for handle in handles {
+                let (tree_data, mesh_data) = handle.await.unwrap();
+                assert_eq!(tree_data, expected, "tree, {party_count} parties");
+                assert_eq!(mesh_data, expected, "mesh, {party_count} parties");
+            }
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn party_with_wrong_key_fails_the_handshake() {
+    let signing_keys: Vec<SigningKey> = (0..3)
+        .map(|party_id| SigningKey::from_bytes(&[party_id as u8 + 7; 32]))
+        .collect();
+    let parties = keyed_participants(&signing_keys[..2], 25200);
+    let options = SetupOptions {
+        accept_timeout: Some(Duration::from_secs(2)),
+        ..Default::default()
+    };
+
+    let server = tokio::spawn({
+        let (parties, signing_key) = (parties.clone(), signing_keys[0].clone());
+        async move { NoisePairWise::with_options(0, parties, &signing_key, &options).await }
+    });
+    // Party 1 holds a key that is not the one listed for it.
+    let impostor = tokio::spawn({
+        let signing_key = signing_keys[2].clone();
+        async move { NoisePairWise::with_options(1, parties, &signing_key, &options).await }
+    });
+
+    let server_error = server.await.unwrap().err().unwrap();
+    let impostor_error = impostor.await.unwrap().err().unwrap();
+    assert!(matches!(
+        server_error.downcast_ref::<Error>(),
+        Some(Error::AcceptTimeout { .. })
+    ));
+    assert!(matches!(
+        impostor_error.downcast_ref::<Error>(),
+        Some(Error::HandshakeFailed { peer: 0 })
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn encrypted_hierarchical_finds_keys_of_both_levels() {
+    let (party_count, chunk_size) = (4, 1000);
+    let signing_keys: Vec<SigningKey> = (0..party_count)
+        .map(|party_id| SigningKey::from_bytes(&[party_id as u8 + 7; 32]))
+        .collect();
+    let mut parties = keyed_participants(&signing_keys, 25300);
+    for (party_id, party) in parties.iter_mut().enumerate() {
+        party.region = Some(["eu", "us"][party_id % 2].to_string());
+    }
+    let options = SetupOptions {
+        deadline: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+
+    let handles: Vec<_> = (0..party_count)
+        .map(|party_id| {
+            let parties = parties.clone();
+            let signing_key = signing_keys[party_id].clone();
+            tokio::spawn(async move {
+                let connector = NoiseConnector::new(TcpConnector, &signing_key, &parties);
+                let hierarchical =
+                    Hierarchical::with_connector(&connector, party_id as Id, parties, &options)
+                        .await
+                        .unwrap();
+                let mut data = vec![0; chunk_size * party_count];
+                data[chunk_size * party_id..chunk_size * (party_id + 1)].fill(party_id as u8 + 1);
+                hierarchical.share(&mut data, chunk_size).await.unwrap();
+                hierarchical.close().await.unwrap();
+                data
+            })
+        })
+        .collect();
+    let expected: Vec<u8> = (0..party_count)
+        .flat_map(|party_id| vec![party_id as u8 + 1; chunk_size])
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), expected);
+    }
+}
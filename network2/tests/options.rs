@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use network2::SetupOptions;
+
+#[test]
+fn backoff_doubles_up_to_the_maximum() {
+    let options = SetupOptions {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+        jitter: false,
+        ..Default::default()
+    };
+    let backoffs: Vec<_> = (0..6).map(|attempt| options.backoff(attempt)).collect();
+    assert_eq!(
+        backoffs,
+        [100, 200, 400, 800, 1000, 1000].map(Duration::from_millis)
+    );
+    // Large attempt counts must not overflow.
+    assert_eq!(options.backoff(u32::MAX), options.max_backoff);
+}
+
+#[test]
+fn jitter_stays_within_half_and_full_backoff() {
+    let options = SetupOptions {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+        jitter: true,
+        ..Default::default()
+    };
+    for attempt in 0..8 {
+        let full = SetupOptions {
+            jitter: false,
+            ..options
+        }
+        .backoff(attempt);
+        for _ in 0..100 {
+            let backoff = options.backoff(attempt);
+            assert!(full / 2 <= backoff && backoff <= full, "{backoff:?}");
+        }
+    }
+}
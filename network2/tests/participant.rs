@@ -0,0 +1,35 @@
+use network2::{Error, Participant, SigningKey};
+
+#[test]
+fn config_lines_carry_optional_region_and_key() {
+    let public_key = SigningKey::from_bytes(&[3; 32]).verifying_key();
+    let hex: String = public_key
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    let path = std::env::temp_dir().join("network2_participant_regions.txt");
+    std::fs::write(
+        &path,
+        format!("3\n127.0.0.1\n127.0.0.2 region=eu {hex}\r\n127.0.0.3 {hex} region=us\n600 200\n"),
+    )
+    .unwrap();
+
+    let parties = Participant::from_file(&path, 1000).unwrap();
+    assert_eq!(parties[0].region, None);
+    assert_eq!(parties[0].public_key, None);
+    assert_eq!(parties[1].region.as_deref(), Some("eu"));
+    assert_eq!(parties[1].public_key, Some(public_key));
+    assert_eq!(parties[2].region.as_deref(), Some("us"));
+    assert_eq!(parties[2].public_key, Some(public_key));
+}
+
+#[test]
+fn default_ports_must_not_overflow() {
+    assert_eq!(Participant::from_default(2, u16::MAX - 1).unwrap().len(), 2);
+    let error = Participant::from_default(3, u16::MAX - 1).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::InvalidConfig(_))
+    ));
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "quic")]
+
+use network2::{Id, Participant, QuicPairWise, QuicTree};
+
+/// Parties on consecutive localhost ports starting at `base_port`.
+fn participants(party_count: usize, base_port: u16) -> Vec<Participant> {
+    Participant::from_default(party_count, base_port).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tree_and_pair_wise_share_over_quic() {
+    let chunk_size = 50_000;
+    for party_count in [1, 3, 4, 6] {
+        let tree_parties = participants(party_count, 21000 + 40 * party_count as u16);
+        let mesh_parties = participants(party_count, 21020 + 40 * party_count as u16);
+        let handles: Vec<_> = (0..party_count)
+            .map(|party_id| {
+                let (tree_parties, mesh_parties) = (tree_parties.clone(), mesh_parties.clone());
+                tokio::spawn(async move {
+                    let own = chunk_size * party_id..chunk_size * (party_id + 1);
+
+                    let tree = QuicTree::new(party_id as Id, tree_parties).await.unwrap();
+                    let mut tree_data = vec![0; chunk_size * party_count];
+                    tree_data[own.clone()].fill(party_id as u8 + 1);
+                    tree.share(&mut tree_data, chunk_size).await.unwrap();
+                    tree.close().await.unwrap();
+
+                    let mesh = QuicPairWise::new(party_id as Id, mesh_parties)
+                        .await
+                        .unwrap();
+                    let mut mesh_data = vec![0; chunk_size * party_count];
+                    mesh_data[own].fill(party_id as u8 + 1);
+                    mesh.share(&mut mesh_data, chunk_size).await.unwrap();
+                    mesh.close().await.unwrap();
+
+                    (tree_data, mesh_data)
+                })
+            })
+            .collect();
+        let expected: Vec<u8> = (0..party_count)
+            .flat_map(|party_id| vec![party_id as u8 + 1; chunk_size])
+            .collect();
+        for handle in handles {
+            let (tree_data, mesh_data) = handle.await.unwrap();
+            assert_eq!(tree_data, expected, "tree, {party_count} parties");
+            assert_eq!(mesh_data, expected, "mesh, {party_count} parties");
+        }
+    }
+}
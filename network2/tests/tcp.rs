@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use network2::{
+    Error, Id, Participant, SetupOptions, TcpHierarchical, TcpPairWise, TcpRing, TcpStar, TcpTree,
+};
+use tokio::{net::TcpStream, time::sleep};
+
+/// Parties on consecutive localhost ports starting at `base_port`.
+fn participants(party_count: usize, base_port: u16) -> Vec<Participant> {
+    Participant::from_default(party_count, base_port).unwrap()
+}
+
+/// Buffer of `party_id` before a share: only its own chunk is filled in.
+fn contribution(party_count: usize, party_id: usize, chunk_size: usize) -> Vec<u8> {
+    let mut data = vec![0; chunk_size * party_count];
+    let own = &mut data[chunk_size * party_id..chunk_size * (party_id + 1)];
+    for (offset, byte) in own.iter_mut().enumerate() {
+        *byte = (party_id * 31 + offset * 7) as u8;
+    }
+    data
+}
+
+/// Buffer every party holds after a share.
+fn shared(party_count: usize, chunk_size: usize) -> Vec<u8> {
+    (0..party_count)
+        .flat_map(|party_id| {
+            let data = contribution(party_count, party_id, chunk_size);
+            data[chunk_size * party_id..chunk_size * (party_id + 1)].to_vec()
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tree_shares_with_any_party_count() {
+    let chunk_size = 1000;
+    for party_count in 1..=13 {
+        let parties = participants(party_count, 20000 + 20 * party_count as u16);
+        let handles: Vec<_> = (0..party_count)
+            .map(|party_id| {
+                let parties = parties.clone();
+                tokio::spawn(async move {
+                    let tree = TcpTree::new(party_id as Id, parties).await.unwrap();
+                    let mut data = contribution(party_count, party_id, chunk_size);
+                    tree.share(&mut data, chunk_size).await.unwrap();
+                    tree.close().await.unwrap();
+                    data
+                })
+            })
+            .collect();
+        let expected = shared(party_count, chunk_size);
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), expected, "{party_count} parties");
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pair_wise_shares_borrowed_buffers() {
+    let chunk_size = 100_000;
+    for party_count in 1..=9 {
+        let parties = participants(party_count, 20400 + 20 * party_count as u16);
+        let handles: Vec<_> = (0..party_count)
+            .map(|party_id| {
+                let parties = parties.clone();
+                tokio::spawn(async move {
+                    let mesh = TcpPairWise::new(party_id as Id, parties).await.unwrap();
+                    let mut data = contribution(party_count, party_id, chunk_size);
+                    mesh.share(&mut data, chunk_size).await.unwrap();
+                    mesh.close().await.unwrap();
+                    data
+                })
+            })
+            .collect();
+        let expected = shared(party_count, chunk_size);
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), expected, "{party_count} parties");
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn ring_shares_over_tcp() {
+    let chunk_size = 300_000;
+    for party_count in [1, 2, 5] {
+        let parties = participants(party_count, 23000 + 100 * party_count as u16);
+        let handles: Vec<_> = (0..party_count)
+            .map(|party_id| {
+                let parties = parties.clone();
+                tokio::spawn(async move {
+                    let ring = TcpRing::new(party_id as Id, parties).await.unwrap();
+                    let mut data = contribution(party_count, party_id, chunk_size);
+                    ring.share(&mut data, chunk_size).await.unwrap();
+                    ring.close().await.unwrap();
+                    data
+                })
+            })
+            .collect();
+        let expected = shared(party_count, chunk_size);
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), expected, "{party_count} parties");
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn star_shares_through_configured_hub() {
+    let (party_count, hub, chunk_size) = (4, 2, 200_000);
+    let parties = participants(party_count, 23600);
+    let handles: Vec<_> = (0..party_count)
+        .map(|party_id| {
+            let parties = parties.clone();
+            tokio::spawn(async move {
+                let options = SetupOptions::default();
+                let star = TcpStar::with_options(party_id as Id, parties, hub, &options)
+                    .await
+                    .unwrap();
+                let mut data = contribution(party_count, party_id, chunk_size);
+                star.share(&mut data, chunk_size).await.unwrap();
+                star.close().await.unwrap();
+                data
+            })
+        })
+        .collect();
+    let expected = shared(party_count, chunk_size);
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), expected);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn hierarchical_shares_across_regions() {
+    let (party_count, chunk_size) = (6, 100_000);
+    let mut parties = participants(party_count, 23700);
+    for (party_id, party) in parties.iter_mut().enumerate() {
+        party.region = Some(["eu", "us", "ap"][party_id % 3].to_string());
+    }
+    let handles: Vec<_> = (0..party_count)
+        .map(|party_id| {
+            let parties = parties.clone();
+            tokio::spawn(async move {
+                let hierarchical = TcpHierarchical::new(party_id as Id, parties).await.unwrap();
+                let mut data = contribution(party_count, party_id, chunk_size);
+                hierarchical.share(&mut data, chunk_size).await.unwrap();
+                hierarchical.close().await.unwrap();
+                data
+            })
+        })
+        .collect();
+    let expected = shared(party_count, chunk_size);
+    for handle in handles {
+        assert_eq!(handle.await.unwrap(), expected);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stray_connections_do_not_stall_setup() {
+    let parties = participants(2, 25000);
+    let server = tokio::spawn({
+        let parties = parties.clone();
+        async move { TcpTree::new(0, parties).await }
+    });
+
+    // One stray closes before announcing an id, the other never announces one.
+    let address = parties[0].address;
+    let closing = loop {
+        match TcpStream::connect(address).await {
+            Ok(tcp_stream) => break tcp_stream,
+            Err(_) => sleep(Duration::from_millis(10)).await,
+        }
+    };
+    drop(closing);
+    let _silent = TcpStream::connect(address).await.unwrap();
+
+    let client = TcpTree::new(1, parties).await.unwrap();
+    let server = server.await.unwrap().unwrap();
+    let (mut server_data, mut client_data) = ([1, 0], [0, 2]);
+    tokio::try_join!(
+        server.share(&mut server_data, 1),
+        client.share(&mut client_data, 1)
+    )
+    .unwrap();
+    assert_eq!((server_data, client_data), ([1, 2], [1, 2]));
+}
+
+#[tokio::test]
+async fn setup_rejects_invalid_configs() {
+    let parties = participants(3, 25200);
+    let mut misnumbered = parties.clone();
+    misnumbered[1].id = 2;
+
+    let errors = [
+        TcpTree::new(0, misnumbered).await.err().unwrap(),
+        TcpPairWise::new(3, parties.clone()).await.err().unwrap(),
+        TcpStar::with_options(0, parties, 3, &SetupOptions::default())
+            .await
+            .err()
+            .unwrap(),
+    ];
+    for error in errors {
+        assert!(
+            matches!(error.downcast_ref::<Error>(), Some(Error::InvalidConfig(_))),
+            "{error:#}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn connect_gives_up_on_a_party_nobody_listens_for() {
+    // Party 0 never starts, so party 1 dials a closed port.
+    let parties = participants(2, 25300);
+    let options = SetupOptions {
+        max_retries: 3,
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let error = TcpTree::with_options(1, parties, &options)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::ConnectTimeout { peer: 0 })
+    ));
+}
+
+#[tokio::test]
+async fn accept_times_out_when_a_peer_never_dials() {
+    // Party 1 never starts, so party 0 waits for it in vain.
+    let parties = participants(2, 25400);
+    let options = SetupOptions {
+        accept_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let error = TcpTree::with_options(0, parties, &options)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::AcceptTimeout { pending: 1 })
+    ));
+}
+
+#[tokio::test]
+async fn setup_times_out_at_the_deadline_while_a_peer_is_absent() {
+    // Party 1 would keep dialing the absent party 0 for far longer than the deadline.
+    let parties = participants(2, 25500);
+    let options = SetupOptions {
+        deadline: Some(Duration::from_millis(300)),
+        ..Default::default()
+    };
+    let error = TcpTree::with_options(1, parties, &options)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error.downcast_ref::<Error>(),
+        Some(Error::SetupTimeout)
+    ));
+}